@@ -0,0 +1,256 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::{
+    components::message::{Msg, MsgType},
+    game::{
+        locale::{tr, trf},
+        Action, Character, Foe, GameState, PropertyType,
+    },
+};
+
+/// Actions in the battle menu.
+const ACTIONS: [&str; 5] = ["attack", "defend", "skill", "item", "flee"];
+
+/// Lists opened from the action menu.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Submenu {
+    Skills,
+    Items,
+}
+
+/// Battle screen with the action menu of the player.
+#[derive(Default)]
+pub struct BattleMenu {
+    selected: usize,
+    /// Open list and its selected row.
+    submenu: Option<(Submenu, usize)>,
+}
+
+impl BattleMenu {
+    /// Handle a key. Return the action chosen by the player.
+    pub fn on_key(&mut self, key: KeyEvent, state: &mut GameState) -> Option<Action> {
+        // read the play-by-play before choosing the next action
+        if state.messages.is_pending() {
+            if key.code == KeyCode::Char(' ') {
+                state.messages.advance();
+            }
+            return None;
+        }
+
+        match key.code {
+            KeyCode::Char('j') => match self.submenu.as_mut() {
+                Some((submenu, row)) => {
+                    let len = choices(*submenu, state).len();
+                    *row = (*row + 1).min(len.saturating_sub(1));
+                }
+                None => self.selected = (self.selected + 1) % ACTIONS.len(),
+            },
+            KeyCode::Char('k') => match self.submenu.as_mut() {
+                Some((_, row)) => *row = row.saturating_sub(1),
+                None => self.selected = (self.selected + ACTIONS.len() - 1) % ACTIONS.len(),
+            },
+            KeyCode::Char(c @ ('h' | 'l')) => {
+                if let Some(battle) = state.battle.as_mut() {
+                    battle.cycle_target(c == 'l');
+                }
+            }
+            KeyCode::Esc => self.submenu = None,
+            KeyCode::Enter => {
+                if let Some((submenu, row)) = self.submenu.take() {
+                    return choices(submenu, state)
+                        .into_iter()
+                        .nth(row)
+                        .map(|(_, action)| action);
+                }
+
+                let submenu = match ACTIONS[self.selected] {
+                    "attack" => return Some(Action::Attack),
+                    "defend" => return Some(Action::Defend),
+                    "skill" => Submenu::Skills,
+                    "item" => Submenu::Items,
+                    _ => return Some(Action::Flee),
+                };
+                if choices(submenu, state).is_empty() {
+                    let text = tr(match submenu {
+                        Submenu::Skills => "battle.no_skills",
+                        Submenu::Items => "battle.no_items",
+                    });
+                    state
+                        .messages
+                        .add_sentence(Msg::markup(MsgType::Battle, &text));
+                } else {
+                    self.submenu = Some((submenu, 0));
+                }
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    pub fn view<B: Backend>(&self, f: &mut Frame<B>, state: &GameState, area: Rect) {
+        let battle = match &state.battle {
+            Some(battle) => battle,
+            None => return,
+        };
+
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(50), Constraint::Ratio(1, 2)].as_ref())
+            .split(area);
+
+        // draw foes and the battle log
+        {
+            let chunks = Layout::default()
+                .constraints(
+                    [
+                        Constraint::Length(battle.foes.len() as u16 * 3 + 2),
+                        Constraint::Min(5),
+                    ]
+                    .as_ref(),
+                )
+                .split(chunks[0]);
+
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title(trf("battle.title", &[&battle.round.to_string()]));
+            let inner = block.inner(chunks[0]);
+            f.render_widget(block, chunks[0]);
+
+            let constraints: Vec<Constraint> =
+                battle.foes.iter().map(|_| Constraint::Length(3)).collect();
+            let rows = Layout::default().constraints(constraints).split(inner);
+            for (i, (foe, row)) in battle.foes.iter().zip(rows).enumerate() {
+                draw_foe(f, foe, i == battle.target, row);
+            }
+
+            state.messages.draw(f, state, chunks[1]);
+        }
+
+        // draw the player and the action menu
+        {
+            let chunks = Layout::default()
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+                .split(chunks[1]);
+
+            state.player.draw_short_desc(f, chunks[0], &state.rules);
+
+            let (items, selected): (Vec<ListItem>, usize) = match self.submenu {
+                Some((submenu, row)) => (
+                    choices(submenu, state)
+                        .into_iter()
+                        .map(|(label, _)| ListItem::new(label))
+                        .collect(),
+                    row,
+                ),
+                None => (
+                    ACTIONS
+                        .iter()
+                        .map(|a| ListItem::new(tr(&format!("battle.action.{}", a))))
+                        .collect(),
+                    self.selected,
+                ),
+            };
+
+            let hint = if state.messages.is_pending() {
+                tr("battle.hint.continue")
+            } else {
+                tr("battle.hint")
+            };
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(hint))
+                .highlight_style(
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .highlight_symbol("> ");
+            let mut list_state = ListState::default();
+            list_state.select(Some(selected));
+            f.render_stateful_widget(list, chunks[1], &mut list_state);
+        }
+    }
+}
+
+/// Rows of a list opened from the action menu, with their actions.
+fn choices(submenu: Submenu, state: &GameState) -> Vec<(String, Action)> {
+    let player = &state.player;
+    match submenu {
+        Submenu::Skills => player
+            .skills()
+            .attacks(&state.rules.skills)
+            .filter(|(_, skill)| {
+                skill
+                    .kind
+                    .allows(player.equipment().weapon(&state.rules.items))
+            })
+            .map(|(_, skill)| {
+                let mut label = format!("{} ({} {}", skill.name, tr("battle.cost"), skill.cost);
+                if skill.neili > 0.0 {
+                    label.push_str(&format!(" {} {}", tr("battle.neili_cost"), skill.neili));
+                }
+                label.push(')');
+                (label, Action::Skill(skill.id.clone()))
+            })
+            .collect(),
+        Submenu::Items => player
+            .inventory()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, s)| {
+                let item = state.rules.items.get(&s.item)?;
+                item.effect
+                    .is_some()
+                    .then(|| (format!("{} ×{}", item.name, s.count), Action::Item(i)))
+            })
+            .collect(),
+    }
+}
+
+fn draw_foe<B: Backend>(f: &mut Frame<B>, foe: &Foe, targeted: bool, area: Rect) {
+    let name_style = if foe.is_down() {
+        Style::default().fg(Color::DarkGray)
+    } else if targeted {
+        Style::default()
+            .fg(Color::Green)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    };
+    let marker = if targeted { "> " } else { "  " };
+
+    let bar = |prop: PropertyType, cur: f64, max: f64, color: Color| {
+        let width = 10;
+        let filled = if max > 0.0 {
+            ((cur / max).clamp(0.0, 1.0) * width as f64).round() as usize
+        } else {
+            0
+        };
+        vec![
+            Span::raw(format!("{} ", prop)),
+            Span::styled("█".repeat(filled), Style::default().fg(color)),
+            Span::styled(
+                "░".repeat(width - filled),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::raw(format!(" {}/{}  ", cur, max)),
+        ]
+    };
+
+    let mut gauges = bar(PropertyType::Jing, foe.jing, foe.max_jing, Color::Red);
+    gauges.extend(bar(PropertyType::Qi, foe.qi, foe.max_qi, Color::Blue));
+
+    let lines = vec![
+        Spans::from(Span::styled(format!("{}{}", marker, foe.name), name_style)),
+        Spans::from(gauges),
+    ];
+    f.render_widget(Paragraph::new(lines), area);
+}
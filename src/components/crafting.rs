@@ -0,0 +1,212 @@
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use crate::game::{
+    locale::{tr, trf},
+    GameState, Ingredient, Recipe,
+};
+
+use super::text;
+
+/// Full-screen list of recipes, opened from the map.
+#[derive(Default)]
+pub struct CraftingMenu {
+    selected: usize,
+}
+
+impl CraftingMenu {
+    pub fn on_key(&mut self, code: char, state: &mut GameState) {
+        let len = state.recipe_library.iter().count();
+        match code {
+            'j' => self.selected = (self.selected + 1).min(len.saturating_sub(1)),
+            'k' => self.selected = self.selected.saturating_sub(1),
+            'm' if len > 0 => state.craft(self.selected),
+            _ => {}
+        }
+    }
+
+    pub fn view<B: Backend>(&self, f: &mut Frame<B>, state: &GameState, area: Rect) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(tr("craft.title"));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .constraints(
+                [
+                    Constraint::Min(3),
+                    Constraint::Length(1),
+                    Constraint::Length(1),
+                ]
+                .as_ref(),
+            )
+            .horizontal_margin(1)
+            .split(inner);
+
+        {
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(40), Constraint::Min(20)].as_ref())
+                .split(chunks[0]);
+
+            // recipes that can be made now are highlighted
+            let rows: Vec<Row> = state
+                .recipe_library
+                .iter()
+                .map(|r| {
+                    let (status, color) = match state.craft_problem(r) {
+                        None => (tr("craft.ready"), Color::White),
+                        Some(_) => (tr("craft.not_ready"), Color::DarkGray),
+                    };
+                    Row::new(vec![r.name.to_string(), status]).style(Style::default().fg(color))
+                })
+                .collect();
+            if rows.is_empty() {
+                f.render_widget(Paragraph::new(tr("craft.none")), chunks[0]);
+            } else {
+                let table = Table::new(rows)
+                    .header(
+                        Row::new(vec![tr("craft.name"), tr("craft.status")])
+                            .style(Style::default().fg(Color::DarkGray)),
+                    )
+                    .widths(&[Constraint::Length(14), Constraint::Length(10)])
+                    .highlight_style(
+                        Style::default()
+                            .fg(Color::Green)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                    .highlight_symbol("> ");
+
+                let mut table_state = TableState::default();
+                table_state.select(Some(self.selected));
+                f.render_stateful_widget(table, chunks[0], &mut table_state);
+            }
+
+            if let Some(recipe) = state.recipe_library.get(self.selected) {
+                let details = describe(recipe, state, chunks[1].width as usize);
+                let details = Paragraph::new(details).block(
+                    Block::default()
+                        .borders(Borders::LEFT)
+                        .title(tr("craft.details")),
+                );
+                f.render_widget(details, chunks[1]);
+            }
+        }
+
+        // show the result of the last action taken here
+        if let Some(msg) = state.messages.current() {
+            f.render_widget(Paragraph::new(msg.to_spans(state)), chunks[1]);
+        }
+
+        let hint = Paragraph::new(tr("craft.hint")).style(Style::default().fg(Color::DarkGray));
+        f.render_widget(hint, chunks[2]);
+    }
+}
+
+/// Details of a recipe, with what is still missing.
+fn describe(recipe: &Recipe, state: &GameState, width: usize) -> Vec<Spans<'static>> {
+    let label = |key: &str| {
+        Span::styled(
+            text::pad(&tr(key), 10),
+            Style::default().fg(Color::DarkGray),
+        )
+    };
+    let items = |list: &[Ingredient]| {
+        let names: Vec<String> = list
+            .iter()
+            .map(|i| {
+                let name = state
+                    .rules
+                    .items
+                    .get(&i.item)
+                    .map_or(i.item.clone(), |item| item.name.to_string());
+                format!("{} ×{}", name, i.count)
+            })
+            .collect();
+        names.join(", ")
+    };
+
+    let mut lines = vec![
+        Spans::from(Span::styled(
+            recipe.name.to_string(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Spans::from(vec![
+            label("craft.inputs"),
+            Span::raw(items(&recipe.inputs)),
+        ]),
+        Spans::from(vec![
+            label("craft.outputs"),
+            Span::raw(items(&recipe.outputs)),
+        ]),
+        Spans::from(vec![
+            label("craft.time"),
+            Span::raw(trf("craft.minutes", &[&recipe.minutes.to_string()])),
+        ]),
+    ];
+
+    if !recipe.tools.is_empty() {
+        let tools: Vec<String> = recipe
+            .tools
+            .iter()
+            .map(|id| {
+                state
+                    .rules
+                    .items
+                    .get(id)
+                    .map_or(id.clone(), |i| i.name.to_string())
+            })
+            .collect();
+        lines.push(Spans::from(vec![
+            label("craft.tools"),
+            Span::raw(tools.join(", ")),
+        ]));
+    }
+    if let Some(station) = &recipe.station {
+        let name = state.world_grid.station_name(station).unwrap_or(station);
+        lines.push(Spans::from(vec![
+            label("craft.station"),
+            Span::raw(name.to_string()),
+        ]));
+    }
+    if !recipe.skills.is_empty() {
+        let mut skills: Vec<String> = recipe
+            .skills
+            .iter()
+            .map(|(id, level)| {
+                let name = state
+                    .rules
+                    .skills
+                    .get(id)
+                    .map_or(id.as_str(), |s| s.name.get());
+                format!("{} {}", name, level)
+            })
+            .collect();
+        skills.sort();
+        lines.push(Spans::from(vec![
+            label("craft.skills"),
+            Span::raw(skills.join(", ")),
+        ]));
+    }
+
+    if let Some(problem) = state.craft_problem(recipe) {
+        lines.push(Spans::from(Span::styled(
+            problem,
+            Style::default().fg(Color::Red),
+        )));
+    }
+
+    lines.push(Spans::default());
+    lines.extend(text::wrap(
+        &Spans::from(recipe.description.to_string()),
+        width.saturating_sub(1),
+    ));
+    lines
+}
@@ -0,0 +1,375 @@
+use assets_manager::AssetCache;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use rand::Rng;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Row, Table, TableState, Tabs},
+    Frame,
+};
+
+use crate::game::{
+    locale::{tr, trf},
+    Attribute, AttributeType, Backgrounds, EventCommand, Message, PropertyType, Rules,
+};
+
+use super::{player::Player, text};
+
+/// Widest name allowed, in terminal columns.
+const NAME_WIDTH: usize = 12;
+/// Attribute value before spending points.
+const POINT_BASE: usize = 8;
+const POINT_MIN: usize = 5;
+const POINT_MAX: usize = 15;
+/// Points to spend on attributes. Lowering an attribute below the base
+/// gives points back.
+const POINT_POOL: usize = 10;
+const DEFAULT_NAME: &str = "方洵";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Name,
+    Attributes,
+    Background,
+    Preview,
+}
+
+impl Step {
+    const ALL: [Step; 4] = [
+        Step::Name,
+        Step::Attributes,
+        Step::Background,
+        Step::Preview,
+    ];
+
+    fn index(&self) -> usize {
+        Self::ALL.iter().position(|s| s == self).unwrap_or(0)
+    }
+
+    fn next(&self) -> Step {
+        Self::ALL[(self.index() + 1).min(Self::ALL.len() - 1)]
+    }
+
+    fn previous(&self) -> Step {
+        Self::ALL[self.index().saturating_sub(1)]
+    }
+
+    fn key(&self) -> &'static str {
+        match self {
+            Step::Name => "name",
+            Step::Attributes => "attributes",
+            Step::Background => "background",
+            Step::Preview => "preview",
+        }
+    }
+}
+
+/// Character creation for a new game.
+pub struct Creation {
+    step: Step,
+    name: String,
+    attr: Attribute,
+    /// Points left to spend.
+    points: usize,
+    /// Attributes were rolled instead of bought with points.
+    rolled: bool,
+    selected_attr: usize,
+    background: usize,
+    backgrounds: Backgrounds,
+    rules: Rules,
+}
+
+impl Creation {
+    pub fn new(cache: &AssetCache) -> Self {
+        let mut creation = Self {
+            step: Step::Name,
+            name: DEFAULT_NAME.to_string(),
+            attr: Attribute::default(),
+            points: 0,
+            rolled: false,
+            selected_attr: 0,
+            background: 0,
+            backgrounds: Backgrounds::load(cache),
+            rules: Rules::load(cache),
+        };
+        creation.point_buy();
+        creation
+    }
+
+    /// The new character and the commands of the chosen background.
+    pub fn finish(self) -> (Player, Vec<EventCommand>) {
+        let player = self.player();
+        let commands = self
+            .backgrounds
+            .get(self.background)
+            .map(|b| b.commands.clone())
+            .unwrap_or_default();
+
+        (player, commands)
+    }
+
+    fn player(&self) -> Player {
+        let mut player = Player::new(self.name.trim(), self.attr.clone());
+        player.recompute(&self.rules);
+        player
+    }
+
+    /// Reset attributes to the base value with a full pool of points.
+    fn point_buy(&mut self) {
+        for t in AttributeType::ALL {
+            self.attr.set(t, POINT_BASE);
+        }
+        self.points = POINT_POOL;
+        self.rolled = false;
+    }
+
+    /// Roll each attribute with three six-sided dice.
+    fn roll(&mut self) {
+        let mut rng = rand::thread_rng();
+        for t in AttributeType::ALL {
+            let value = (0..3).map(|_| rng.gen_range(1..=6)).sum();
+            self.attr.set(t, value);
+        }
+        self.points = 0;
+        self.rolled = true;
+    }
+
+    /// Raise or lower the selected attribute by one point.
+    fn adjust(&mut self, raise: bool) {
+        if self.rolled {
+            return;
+        }
+
+        let t = AttributeType::ALL[self.selected_attr];
+        let value = self.attr.get(t);
+        if raise && self.points > 0 && value < POINT_MAX {
+            self.attr.set(t, value + 1);
+            self.points -= 1;
+        } else if !raise && value > POINT_MIN {
+            self.attr.set(t, value - 1);
+            self.points += 1;
+        }
+    }
+
+    fn can_continue(&self) -> bool {
+        match self.step {
+            Step::Name => !self.name.trim().is_empty(),
+            Step::Attributes => self.points == 0,
+            Step::Background => !self.backgrounds.is_empty(),
+            Step::Preview => true,
+        }
+    }
+
+    pub fn on_key(&mut self, key: KeyEvent) -> Option<Message> {
+        match key.code {
+            KeyCode::Esc if self.step == Step::Name => return Some(Message::CancelCreation),
+            KeyCode::Esc => self.step = self.step.previous(),
+            KeyCode::Enter if !self.can_continue() => {}
+            KeyCode::Enter if self.step == Step::Preview => return Some(Message::StartGame),
+            KeyCode::Enter => self.step = self.step.next(),
+            KeyCode::Backspace if self.step == Step::Name => {
+                self.name.pop();
+            }
+            KeyCode::Char(_) if key.modifiers.contains(KeyModifiers::CONTROL) => {}
+            KeyCode::Char(c) => match self.step {
+                Step::Name => {
+                    let name = format!("{}{}", self.name, c);
+                    if !c.is_control() && text::width(&name) <= NAME_WIDTH {
+                        self.name = name;
+                    }
+                }
+                Step::Attributes => {
+                    let len = AttributeType::ALL.len();
+                    match c {
+                        'j' => self.selected_attr = (self.selected_attr + 1) % len,
+                        'k' => self.selected_attr = (self.selected_attr + len - 1) % len,
+                        'h' | 'l' => self.adjust(c == 'l'),
+                        'r' => self.roll(),
+                        'p' => self.point_buy(),
+                        _ => {}
+                    }
+                }
+                Step::Background => {
+                    let len = self.backgrounds.len().max(1);
+                    match c {
+                        'j' => self.background = (self.background + 1) % len,
+                        'k' => self.background = (self.background + len - 1) % len,
+                        _ => {}
+                    }
+                }
+                Step::Preview => {}
+            },
+            _ => {}
+        }
+
+        None
+    }
+
+    pub fn view<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(tr("creation.title"));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .constraints(
+                [
+                    Constraint::Length(2),
+                    Constraint::Min(3),
+                    Constraint::Length(1),
+                ]
+                .as_ref(),
+            )
+            .horizontal_margin(1)
+            .split(inner);
+
+        // draw steps
+        {
+            let titles = Step::ALL
+                .iter()
+                .map(|s| Spans::from(tr(&format!("creation.step.{}", s.key()))))
+                .collect();
+            let tabs = Tabs::new(titles)
+                .select(self.step.index())
+                .divider("›")
+                .highlight_style(
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                );
+            f.render_widget(tabs, chunks[0]);
+        }
+
+        match self.step {
+            Step::Name => self.view_name(f, chunks[1]),
+            Step::Attributes => self.view_attributes(f, chunks[1]),
+            Step::Background => self.view_background(f, chunks[1]),
+            Step::Preview => self.view_preview(f, chunks[1]),
+        }
+
+        // draw key hints
+        let hint = Paragraph::new(tr(&format!("creation.hint.{}", self.step.key())))
+            .style(Style::default().fg(Color::DarkGray));
+        f.render_widget(hint, chunks[2]);
+    }
+
+    fn view_name<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let line = Spans::from(vec![
+            Span::raw(tr("creation.name")),
+            Span::styled(
+                format!("{}_", self.name),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+        ]);
+        f.render_widget(Paragraph::new(line), area);
+    }
+
+    fn view_attributes<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let rows: Vec<Row> = AttributeType::ALL
+            .iter()
+            .map(|t| {
+                let value = self.attr.get(*t).to_string();
+                let value = if self.rolled {
+                    value
+                } else {
+                    format!("< {} >", value)
+                };
+                Row::new(vec![t.to_string(), value])
+            })
+            .collect();
+
+        let title = if self.rolled {
+            tr("creation.rolled")
+        } else {
+            trf("creation.points", &[&self.points.to_string()])
+        };
+        let table = Table::new(rows)
+            .block(Block::default().borders(Borders::TOP).title(title))
+            .widths(&[Constraint::Length(16), Constraint::Length(8)])
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("> ");
+
+        let mut state = TableState::default();
+        state.select(Some(self.selected_attr));
+        f.render_stateful_widget(table, area, &mut state);
+    }
+
+    fn view_background<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(16), Constraint::Min(10)].as_ref())
+            .split(area);
+
+        let items: Vec<ListItem> = self
+            .backgrounds
+            .iter()
+            .map(|b| ListItem::new(b.name.get()))
+            .collect();
+        let list = List::new(items)
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("> ");
+        let mut state = ListState::default();
+        state.select(Some(self.background));
+        f.render_stateful_widget(list, chunks[0], &mut state);
+
+        if let Some(background) = self.backgrounds.get(self.background) {
+            let lines = text::wrap(
+                &Spans::from(background.description.get()),
+                chunks[1].width as usize,
+            );
+            f.render_widget(Paragraph::new(lines), chunks[1]);
+        }
+    }
+
+    fn view_preview<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let player = self.player();
+        let label = |key: &str| Span::raw(text::pad(&tr(key), 12));
+        let mut lines = vec![Spans::from(vec![
+            label("creation.step.name"),
+            Span::styled(
+                player.name().to_string(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+        ])];
+
+        if let Some(background) = self.backgrounds.get(self.background) {
+            lines.push(Spans::from(vec![
+                label("creation.step.background"),
+                Span::raw(background.name.to_string()),
+            ]));
+        }
+        lines.push(Spans::default());
+
+        for t in AttributeType::ALL {
+            lines.push(Spans::from(vec![
+                Span::raw(text::pad(&t.to_string(), 12)),
+                Span::raw(player.attr().get(t).to_string()),
+            ]));
+        }
+        lines.push(Spans::default());
+
+        for (prop, color) in [
+            (PropertyType::MaxJing, Color::Red),
+            (PropertyType::MaxQi, Color::Blue),
+            (PropertyType::MaxShen, Color::Yellow),
+        ] {
+            lines.push(Spans::from(vec![
+                Span::styled(text::pad(&prop.to_string(), 12), Style::default().fg(color)),
+                Span::raw(player.prop().number(&prop).to_string()),
+            ]));
+        }
+
+        f.render_widget(Paragraph::new(lines), area);
+    }
+}
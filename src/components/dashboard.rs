@@ -10,11 +10,11 @@ impl Default for Dashboard {
     fn default() -> Dashboard {
         Self {
             menu: Menu::with_items(vec![
-                "再入江湖",
-                "初出茅庐",
-                "天工造物",
-                "游戏设置",
-                "退隐山林",
+                "menu.continue",
+                "menu.new_game",
+                "menu.workshop",
+                "menu.settings",
+                "menu.quit",
             ]),
         }
     }
@@ -22,7 +22,7 @@ impl Default for Dashboard {
 
 impl Dashboard {
     pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
-        f.render_widget(self.menu.clone(), area);
+        f.render_widget(self.menu.localized(), area);
     }
 
     pub fn on_key(&mut self, code: char) {
@@ -0,0 +1,241 @@
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use crate::{
+    components::message::{Msg, MsgType},
+    game::{
+        locale::{tr, trf},
+        EquipSlot, GameState, Item, ItemStack, Money, INVENTORY_SLOTS,
+    },
+};
+
+use super::text;
+
+/// Full-screen inventory, opened from the map.
+#[derive(Default)]
+pub struct InventoryMenu {
+    selected: usize,
+}
+
+impl InventoryMenu {
+    pub fn on_key(&mut self, code: char, state: &mut GameState) {
+        let len = state.player.inventory().len();
+        match code {
+            'j' => self.selected = (self.selected + 1).min(len.saturating_sub(1)),
+            'k' => self.selected = self.selected.saturating_sub(1),
+            'u' if len > 0 => {
+                let text = match state.use_item(self.selected) {
+                    Ok(text) | Err(text) => text,
+                };
+                state
+                    .messages
+                    .add_sentence(Msg::markup(MsgType::System, &text));
+            }
+            'e' if len > 0 => state.equip(self.selected),
+            'd' if len > 0 => state.drop_item(self.selected),
+            _ => {}
+        }
+
+        // keep the selection on the list when a slot is gone
+        let len = state.player.inventory().len();
+        self.selected = self.selected.min(len.saturating_sub(1));
+    }
+
+    pub fn view<B: Backend>(&self, f: &mut Frame<B>, state: &GameState, area: Rect) {
+        let player = &state.player;
+        let inventory = player.inventory();
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(tr("inventory.title"));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .constraints(
+                [
+                    Constraint::Length(2),
+                    Constraint::Min(3),
+                    Constraint::Length(1),
+                    Constraint::Length(1),
+                ]
+                .as_ref(),
+            )
+            .horizontal_margin(1)
+            .split(inner);
+
+        // draw slots and weight in use
+        {
+            let capacity = trf(
+                "inventory.capacity",
+                &[
+                    &inventory.len().to_string(),
+                    &INVENTORY_SLOTS.to_string(),
+                    &format!("{:.1}", inventory.weight(&state.rules.items)),
+                    &format!("{:.1}", player.max_weight()),
+                ],
+            );
+            f.render_widget(Paragraph::new(capacity), chunks[0]);
+        }
+
+        {
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(40), Constraint::Min(20)].as_ref())
+                .split(chunks[1]);
+
+            if inventory.is_empty() {
+                f.render_widget(Paragraph::new(tr("inventory.empty")), chunks[0]);
+            } else {
+                let rows: Vec<Row> = inventory
+                    .iter()
+                    .map(|s| match state.rules.items.get(&s.item) {
+                        Some(item) => Row::new(vec![
+                            item.name.to_string(),
+                            s.count.to_string(),
+                            item.category.to_string(),
+                        ]),
+                        None => Row::new(vec![s.item.clone(), s.count.to_string()]),
+                    })
+                    .collect();
+                let table = Table::new(rows)
+                    .header(
+                        Row::new(vec![
+                            tr("inventory.name"),
+                            tr("inventory.count"),
+                            tr("inventory.category"),
+                        ])
+                        .style(Style::default().fg(Color::DarkGray)),
+                    )
+                    .widths(&[
+                        Constraint::Length(14),
+                        Constraint::Length(8),
+                        Constraint::Length(8),
+                    ])
+                    .highlight_style(
+                        Style::default()
+                            .fg(Color::Green)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                    .highlight_symbol("> ");
+
+                let mut table_state = TableState::default();
+                table_state.select(Some(self.selected));
+                f.render_stateful_widget(table, chunks[0], &mut table_state);
+            }
+
+            let selected = inventory
+                .get(self.selected)
+                .and_then(|s| state.rules.items.get(&s.item).map(|item| (s, item)));
+            if let Some((stack, item)) = selected {
+                let details = describe(stack, item, state, chunks[1].width as usize);
+                let details = Paragraph::new(details).block(
+                    Block::default()
+                        .borders(Borders::LEFT)
+                        .title(tr("inventory.details")),
+                );
+                f.render_widget(details, chunks[1]);
+            }
+        }
+
+        // show the result of the last action taken here
+        if let Some(msg) = state.messages.current() {
+            f.render_widget(Paragraph::new(msg.to_spans(state)), chunks[2]);
+        }
+
+        let hint = Paragraph::new(tr("inventory.hint")).style(Style::default().fg(Color::DarkGray));
+        f.render_widget(hint, chunks[3]);
+    }
+}
+
+/// Details of an item to inspect it.
+fn describe(
+    stack: &ItemStack,
+    item: &Item,
+    state: &GameState,
+    width: usize,
+) -> Vec<Spans<'static>> {
+    let label = |key: &str| {
+        Span::styled(
+            text::pad(&tr(key), 10),
+            Style::default().fg(Color::DarkGray),
+        )
+    };
+
+    let mut lines = vec![
+        Spans::from(Span::styled(
+            item.name.to_string(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Spans::from(vec![
+            label("inventory.category"),
+            Span::raw(item.category.to_string()),
+        ]),
+        Spans::from(vec![
+            label("inventory.weight"),
+            Span::raw(format!(
+                "{} ({:.1})",
+                item.weight,
+                item.weight * stack.count as f64
+            )),
+        ]),
+        Spans::from(vec![
+            label("inventory.stack"),
+            Span::raw(format!("{}/{}", stack.count, item.stack)),
+        ]),
+    ];
+
+    if item.price > 0 {
+        lines.push(Spans::from(vec![
+            label("inventory.price"),
+            Span::raw(Money(item.price).to_string()),
+        ]));
+    }
+
+    if let Some(slot) = EquipSlot::of(item.category) {
+        let kind = item.weapon.map(|w| format!(" ({})", w)).unwrap_or_default();
+        lines.push(Spans::from(vec![
+            label("inventory.slot"),
+            Span::raw(format!("{}{}", slot, kind)),
+        ]));
+        let bonuses: Vec<String> = item.modifiers.iter().map(|m| m.to_string()).collect();
+        lines.push(Spans::from(vec![
+            label("inventory.bonus"),
+            Span::raw(bonuses.join(", ")),
+        ]));
+    }
+
+    if let Some(effect) = &item.effect {
+        let mut effects: Vec<String> = effect
+            .restore
+            .iter()
+            .map(|(prop, amount)| format!("{} {:+}", prop, amount))
+            .collect();
+        effects.extend(effect.cure.iter().map(|(kind, amount)| {
+            trf("inventory.cure", &[&kind.to_string(), &amount.to_string()])
+        }));
+        effects.extend(
+            effect
+                .buffs
+                .iter()
+                .filter_map(|id| state.rules.buffs.get(id))
+                .map(|b| b.name.to_string()),
+        );
+        lines.push(Spans::from(vec![
+            label("inventory.effect"),
+            Span::raw(effects.join(", ")),
+        ]));
+    }
+
+    lines.push(Spans::default());
+    lines.extend(text::wrap(
+        &Spans::from(item.description.to_string()),
+        width.saturating_sub(1),
+    ));
+    lines
+}
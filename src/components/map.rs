@@ -1,8 +1,9 @@
 use crate::{
     components::Direction,
-    game::{Character, GameState},
+    game::{locale::Text, Character, GameState, GameSwitch},
 };
 use assets_manager::{loader, Asset, AssetCache};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use tui::{
     backend::Backend,
@@ -14,9 +15,12 @@ use tui::{
     Frame,
 };
 
-use super::player::Player;
+use super::{player::Player, text};
 
-#[derive(Deserialize)]
+/// Columns taken by one tile on screen, so that wide labels fit.
+const TILE_WIDTH: usize = 2;
+
+#[derive(Clone, Default, Deserialize)]
 pub struct Tiles {
     pub data: Vec<Tile>,
 }
@@ -29,12 +33,38 @@ impl Asset for Tiles {
 
 #[derive(Default, Deserialize, Clone)]
 pub struct Tile {
-    pub name: String,
+    pub name: Text,
     pub label: String,
     pub passing: Passing,
+    /// Crafting station, e.g. `stove`, used by recipes.
+    #[serde(default)]
+    pub station: Option<String>,
+    /// Id of a resource node to gather from the tile.
+    #[serde(default)]
+    pub resource: Option<String>,
+    /// Index of the tile it turns into when the player walks into it,
+    /// such as a closed door opening.
+    #[serde(default)]
+    pub opens: Option<usize>,
+    /// What the player needs to enter the tile, besides `passing`.
+    #[serde(default)]
+    pub passage: Option<Passage>,
 }
 
-#[derive(Default, Deserialize, Clone)]
+impl Tile {
+    /// The tile can be entered from at least one side.
+    pub fn is_open(&self) -> bool {
+        let Passing {
+            left,
+            right,
+            top,
+            down,
+        } = self.passing;
+        left || right || top || down
+    }
+}
+
+#[derive(Default, Deserialize, Clone, Copy)]
 pub struct Passing {
     pub left: bool,
     pub right: bool,
@@ -42,13 +72,149 @@ pub struct Passing {
     pub down: bool,
 }
 
+/// Something the player needs to pass, e.g. `{ "Item": "raft" }`.
+#[derive(Clone, Deserialize)]
+pub enum Requirement {
+    /// A switch that is on.
+    Switch(String),
+    /// An item carried.
+    Item(String),
+    /// A skill of at least a level.
+    Skill(String, u32),
+}
+
+impl Requirement {
+    pub fn is_met(&self, player: &Player, switches: &GameSwitch) -> bool {
+        match self {
+            Requirement::Switch(name) => switches.is_on(name),
+            Requirement::Item(id) => player.inventory().count(id) > 0,
+            Requirement::Skill(id, level) => player.skills().level(id) >= *level,
+        }
+    }
+}
+
+/// Requirements to enter a tile or an area, with what to tell the
+/// player when they are not met, e.g.
+/// `{ "requires": [{ "Item": "raft" }], "refusal": { "zh": "水流湍急，没有竹筏过不去。", "en": "..." } }`.
+#[derive(Default, Clone, Deserialize)]
+pub struct Passage {
+    pub requires: Vec<Requirement>,
+    pub refusal: Text,
+}
+
+impl Passage {
+    pub fn allows(&self, player: &Player, switches: &GameSwitch) -> bool {
+        self.requires.iter().all(|r| r.is_met(player, switches))
+    }
+}
+
+/// An area of the map the player can only enter with a passage, e.g.
+/// `{ "from": [47, 26], "to": [49, 38], "requires": [{ "Switch": "boar_defeated" }], "refusal": "..." }`.
+#[derive(Default, Clone, Deserialize)]
+pub struct Barrier {
+    #[serde(flatten)]
+    pub area: Area,
+    #[serde(flatten)]
+    pub passage: Passage,
+}
+
+/// What came of trying to move the player.
+pub enum Step {
+    Moved,
+    /// The tiles do not let anyone through.
+    Blocked,
+    /// A passage was not allowed, with the reason.
+    Refused(String),
+}
+
 #[derive(Default, Clone, Deserialize)]
 pub struct World {
     pub blocks: Vec<Vec<usize>>,
     #[serde(skip)]
     pub tiles: Vec<Tile>,
-    pub name: String,
-    pub region: String,
+    pub name: Text,
+    pub region: Text,
+    /// Creatures on the map. The asset lists those placed by hand,
+    /// spawned ones are added while playing.
+    #[serde(default)]
+    pub creatures: Vec<Creature>,
+    #[serde(default)]
+    pub spawns: Vec<SpawnZone>,
+    /// Merchants on the map. Walking into one opens the shop.
+    #[serde(default)]
+    pub stalls: Vec<Stall>,
+    /// Areas with requirements to enter them.
+    #[serde(default)]
+    pub barriers: Vec<Barrier>,
+    /// Where the player lives and can sleep.
+    #[serde(default)]
+    pub home: Option<Area>,
+    /// Safe place where the player wakes up after fainting.
+    #[serde(default)]
+    pub spawn: Option<(usize, usize)>,
+}
+
+/// An enemy standing on the map. Walking into it starts a battle.
+#[derive(Default, Clone, Deserialize)]
+pub struct Creature {
+    /// Enemy id.
+    pub enemy: String,
+    pub x: usize,
+    pub y: usize,
+    /// Index of the spawn zone it came from.
+    #[serde(skip)]
+    pub zone: Option<usize>,
+}
+
+/// Where a merchant stands, e.g. `{ "merchant": "zhou_store", "x": 6, "y": 2 }`.
+#[derive(Default, Clone, Deserialize)]
+pub struct Stall {
+    /// Merchant id.
+    pub merchant: String,
+    pub x: usize,
+    pub y: usize,
+}
+
+/// A rectangle on the map, e.g. `{ "from": [40, 32], "to": [44, 35] }`.
+#[derive(Default, Clone, Deserialize)]
+pub struct Area {
+    /// Top left corner.
+    pub from: (usize, usize),
+    /// Bottom right corner, included.
+    pub to: (usize, usize),
+}
+
+impl Area {
+    pub fn contains(&self, (x, y): (usize, usize)) -> bool {
+        (self.from.0..=self.to.0).contains(&x) && (self.from.1..=self.to.1).contains(&y)
+    }
+
+    /// True if the corners are swapped, so no position is inside.
+    pub fn is_empty(&self) -> bool {
+        self.from.0 > self.to.0 || self.from.1 > self.to.1
+    }
+
+    /// A random position inside a non-empty area.
+    pub fn random_pos<R: Rng>(&self, rng: &mut R) -> (usize, usize) {
+        (
+            rng.gen_range(self.from.0..=self.to.0),
+            rng.gen_range(self.from.1..=self.to.1),
+        )
+    }
+}
+
+/// An area where creatures appear over time, e.g.
+/// `{ "enemies": ["rabbit"], "from": [20, 10], "to": [45, 25], "max": 3, "chance": 0.05 }`.
+#[derive(Default, Clone, Deserialize)]
+pub struct SpawnZone {
+    /// Enemy ids to pick from.
+    pub enemies: Vec<String>,
+    #[serde(flatten)]
+    pub area: Area,
+    /// Most creatures from this zone on the map at once.
+    pub max: usize,
+    /// Chance between 0 and 1 to spawn a creature each turn.
+    pub chance: f64,
 }
 
 impl Asset for World {
@@ -76,6 +242,146 @@ impl World {
         self.tiles = new.to_owned();
     }
 
+    /// Replace the tile at a position. Return false if the position is
+    /// outside the map or the tile is unknown.
+    pub fn set_tile(&mut self, (x, y): (usize, usize), tile: usize) -> bool {
+        if tile >= self.tiles.len() {
+            return false;
+        }
+        match self.blocks.get_mut(y).and_then(|row| row.get_mut(x)) {
+            Some(block) => {
+                *block = tile;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn creature_at(&self, pos: (usize, usize)) -> Option<&Creature> {
+        self.creatures.iter().find(|c| (c.x, c.y) == pos)
+    }
+
+    pub fn tile_at(&self, (x, y): (usize, usize)) -> Option<&Tile> {
+        self.blocks
+            .get(y)
+            .and_then(|row| row.get(x))
+            .and_then(|b| self.tiles.get(*b))
+    }
+
+    /// A position and the positions next to it on the map.
+    fn around(&self, pos: (usize, usize)) -> Vec<(usize, usize)> {
+        let directions = [
+            Direction::Left,
+            Direction::Right,
+            Direction::Up,
+            Direction::Down,
+        ];
+        let mut around = vec![pos];
+        around.extend(directions.iter().filter_map(|d| self.neighbor(pos, d)));
+        around
+    }
+
+    /// Stations of the tile at a position and the tiles next to it.
+    pub fn stations_near(&self, pos: (usize, usize)) -> Vec<&str> {
+        self.around(pos)
+            .into_iter()
+            .filter_map(|p| self.tile_at(p))
+            .filter_map(|t| t.station.as_deref())
+            .collect()
+    }
+
+    /// Resource nodes on the tile at a position and the tiles next to
+    /// it, with their positions.
+    pub fn resources_near(&self, pos: (usize, usize)) -> Vec<((usize, usize), &str)> {
+        self.around(pos)
+            .into_iter()
+            .filter_map(|p| Some((p, self.tile_at(p)?.resource.as_deref()?)))
+            .collect()
+    }
+
+    /// Name of a tile with a station.
+    pub fn station_name(&self, station: &str) -> Option<&str> {
+        self.tiles
+            .iter()
+            .find(|t| t.station.as_deref() == Some(station))
+            .map(|t| t.name.get())
+    }
+
+    pub fn stall_at(&self, pos: (usize, usize)) -> Option<&Stall> {
+        self.stalls.iter().find(|s| (s.x, s.y) == pos)
+    }
+
+    /// Creatures within a distance of a position.
+    pub fn creatures_near(&self, pos: (usize, usize), range: usize) -> Vec<&Creature> {
+        self.creatures
+            .iter()
+            .filter(|c| {
+                let (dx, dy) = (c.x as f64 - pos.0 as f64, c.y as f64 - pos.1 as f64);
+                (dx * dx + dy * dy).sqrt() <= range as f64
+            })
+            .collect()
+    }
+
+    pub fn is_home(&self, pos: (usize, usize)) -> bool {
+        self.home.as_ref().is_some_and(|h| h.contains(pos))
+    }
+
+    pub fn remove_creature(&mut self, pos: (usize, usize)) {
+        self.creatures.retain(|c| (c.x, c.y) != pos);
+    }
+
+    /// The position next to another one, if it is on the map.
+    pub fn neighbor(&self, pos: (usize, usize), direction: &Direction) -> Option<(usize, usize)> {
+        let (x, y) = pos;
+        let (width, height) = (
+            self.blocks.first().map_or(0, |r| r.len()),
+            self.blocks.len(),
+        );
+        match direction {
+            Direction::Left if x > 0 => Some((x - 1, y)),
+            Direction::Right if x + 1 < width => Some((x + 1, y)),
+            Direction::Up if y > 0 => Some((x, y - 1)),
+            Direction::Down if y + 1 < height => Some((x, y + 1)),
+            _ => None,
+        }
+    }
+
+    /// Roll each spawn zone for a new creature. Creatures only appear on
+    /// open tiles away from the player and other creatures.
+    pub fn spawn(&mut self, player: (usize, usize)) {
+        let mut rng = rand::thread_rng();
+        let mut spawned = vec![];
+
+        for (i, zone) in self.spawns.iter().enumerate() {
+            let count = self.creatures.iter().filter(|c| c.zone == Some(i)).count();
+            if zone.enemies.is_empty()
+                || count >= zone.max
+                || zone.area.is_empty()
+                || !rng.gen_bool(zone.chance.clamp(0.0, 1.0))
+            {
+                continue;
+            }
+
+            let (x, y) = zone.area.random_pos(&mut rng);
+            let open = self.tile_at((x, y)).is_some_and(|t| t.is_open());
+            if open
+                && (x, y) != player
+                && self.creature_at((x, y)).is_none()
+                && self.stall_at((x, y)).is_none()
+            {
+                let enemy = zone.enemies[rng.gen_range(0..zone.enemies.len())].clone();
+                spawned.push(Creature {
+                    enemy,
+                    x,
+                    y,
+                    zone: Some(i),
+                });
+            }
+        }
+
+        self.creatures.extend(spawned);
+    }
+
     pub fn draw_info<B: Backend>(&self, f: &mut Frame<B>, state: &GameState, area: Rect) {
         let style = Style::default().fg(Color::Cyan);
         let name = Paragraph::new(Spans::from(vec![
@@ -111,20 +417,16 @@ impl World {
             let width = blocks[0].len();
             let display_range = (3 * range) as f64;
             let pos = player.get_pos();
+            let now = setting.world_state.clock.total_minutes();
+            let map = setting.curr_map.as_ref().map_or("", |m| m.map_file());
 
             // find visible tiles relative to current pos
             let (x_start, x_end, y_start, y_end) = {
                 let r = display_range as usize;
                 (
-                    match pos.0.checked_sub(r) {
-                        Some(start) => start,
-                        None => 0,
-                    },
+                    pos.0.saturating_sub(r),
                     if pos.0 + r > width { width } else { pos.0 + r },
-                    match pos.1.checked_sub(r) {
-                        Some(start) => start,
-                        None => 0,
-                    },
+                    pos.1.saturating_sub(r),
                     if pos.1 + r > height {
                         height
                     } else {
@@ -144,20 +446,41 @@ impl World {
                     // ignore block if it is not within the display range
                     // default tile is the one with index 0
                     let tile = &tiles[blocks[y][x]];
+                    // creatures can only be seen within the visible range
+                    let creature = self
+                        .creature_at((x, y))
+                        .filter(|_| distance <= range as f64)
+                        .and_then(|c| setting.enemy_library.get(&c.enemy));
+                    let merchant = self
+                        .stall_at((x, y))
+                        .and_then(|s| setting.merchant_library.get(&s.merchant));
                     let label = if pos == (x, y) {
                         player.symbol()
+                    } else if let Some(enemy) = creature {
+                        &enemy.glyph
+                    } else if let Some(merchant) = merchant {
+                        &merchant.glyph
                     } else {
                         &tile.label
                     };
                     // check if tile is in visible range
                     let style = if pos == (x, y) {
                         Style::default().fg(Color::Cyan)
+                    } else if creature.is_some() {
+                        Style::default().fg(Color::Red)
+                    } else if merchant.is_some() {
+                        Style::default().fg(Color::Yellow)
+                    } else if tile.resource.is_some()
+                        && setting.harvests.is_depleted(map, (x, y), now)
+                    {
+                        // gathered nodes look like out of sight until they respawn
+                        Style::default().fg(Color::DarkGray)
                     } else if distance <= range as f64 {
                         Style::default().fg(Color::White)
                     } else {
                         Style::default().fg(Color::DarkGray)
                     };
-                    row.push(Span::styled(format!("{} ", label), style));
+                    row.push(Span::styled(text::pad(label, TILE_WIDTH), style));
                 }
                 grid.push(Spans::from(row));
             }
@@ -168,48 +491,55 @@ impl World {
         }
     }
 
-    pub fn player_move(&self, player: &mut Player, direction: Direction) {
-        let (x, y) = player.get_pos();
-        let Self { blocks, tiles, .. } = self;
-        if !blocks.is_empty() {
-            let width = blocks[0].len();
-            let height = blocks.len();
+    /// Move the player if the tiles allow it and the player meets the
+    /// requirements of the tile and of any barrier around it.
+    pub fn player_move(
+        &self,
+        player: &mut Player,
+        direction: Direction,
+        switches: &GameSwitch,
+    ) -> Step {
+        let pos = player.get_pos();
+        let target = match self.neighbor(pos, &direction) {
+            Some(target) => target,
+            None => return Step::Blocked,
+        };
+        let (from, to) = match (self.tile_at(pos), self.tile_at(target)) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return Step::Blocked,
+        };
 
-            match direction {
-                Direction::Left => {
-                    if x > 0
-                        && tiles[blocks[y][x]].passing.left
-                        && tiles[blocks[y][x - 1]].passing.right
-                    {
-                        player.move_left();
-                    }
-                }
-                Direction::Right => {
-                    if x < width - 1
-                        && tiles[blocks[y][x]].passing.right
-                        && tiles[blocks[y][x + 1]].passing.left
-                    {
-                        player.move_right();
-                    }
-                }
-                Direction::Up => {
-                    if y > 0
-                        && tiles[blocks[y][x]].passing.top
-                        && tiles[blocks[y - 1][x]].passing.down
-                    {
-                        player.move_up();
-                    }
-                }
-                Direction::Down => {
-                    if y < height - 1
-                        && tiles[blocks[y][x]].passing.down
-                        && tiles[blocks[y + 1][x]].passing.top
-                    {
-                        player.move_down();
-                    }
-                }
-            }
+        let passable = match direction {
+            Direction::Left => from.passing.left && to.passing.right,
+            Direction::Right => from.passing.right && to.passing.left,
+            Direction::Up => from.passing.top && to.passing.down,
+            Direction::Down => from.passing.down && to.passing.top,
+        };
+        if !passable {
+            return Step::Blocked;
+        }
+
+        let refused = to
+            .passage
+            .iter()
+            .chain(
+                self.barriers
+                    .iter()
+                    .filter(|b| b.area.contains(target))
+                    .map(|b| &b.passage),
+            )
+            .find(|p| !p.allows(player, switches));
+        if let Some(passage) = refused {
+            return Step::Refused(passage.refusal.to_string());
+        }
+
+        match direction {
+            Direction::Left => player.move_left(),
+            Direction::Right => player.move_right(),
+            Direction::Up => player.move_up(),
+            Direction::Down => player.move_down(),
         }
+        Step::Moved
     }
 }
 
@@ -1,88 +1,402 @@
-use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use assets_manager::{loader, Asset, AssetCache};
+use serde::{Deserialize, Deserializer, Serialize};
 use tui::{
     backend::Backend,
     layout::Rect,
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::{Span, Spans},
     widgets::{Block, Borders, Paragraph},
     Frame,
 };
 
+use crate::game::{
+    locale::{self, tr},
+    markup, template, GameState,
+};
+
+use super::text;
+
+/// Number of characters revealed on each tick by the typewriter effect.
+const TYPING_SPEED: usize = 8;
+
+fn default_typing_speed() -> usize {
+    TYPING_SPEED
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct MessageSystem {
     messages: Vec<Msg>,
+    /// Story messages waiting for the player to continue.
+    #[serde(default)]
+    pending: VecDeque<Msg>,
+    /// Characters revealed of the last message. None if it is fully shown.
+    #[serde(default)]
+    revealed: Option<usize>,
+    /// Characters revealed per tick. 0 disables the typewriter effect.
+    #[serde(default = "default_typing_speed")]
+    typing_speed: usize,
 }
 
 impl Default for MessageSystem {
     fn default() -> Self {
-        Self { messages: vec![] }
+        Self {
+            messages: vec![],
+            pending: VecDeque::new(),
+            revealed: None,
+            typing_speed: TYPING_SPEED,
+        }
     }
 }
 
 impl MessageSystem {
+    /// Show a message right away, or after the story sequence being
+    /// read so that it does not interrupt it.
     pub fn add_sentence(&mut self, msg: Msg) {
-        self.messages.push(msg);
+        if self.is_pending() {
+            self.pending.push_back(msg);
+        } else {
+            self.messages.push(msg);
+        }
     }
 
+    /// Show several messages in order, like [`MessageSystem::add_sentence`].
     pub fn add_sentences(&mut self, bunch: Vec<Msg>) {
         for msg in bunch {
             self.add_sentence(msg);
         }
     }
 
-    pub fn draw<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
-        // find all messages in the tubes of comb_id
-        let msgs: Vec<Spans> = self.messages.iter().map(|m| m.to_spans()).collect();
+    /// Queue story messages to be revealed one at a time.
+    ///
+    /// The first message is shown right away if no other message is
+    /// waiting, the rest wait for [`MessageSystem::advance`].
+    pub fn queue_sentences(&mut self, bunch: Vec<Msg>) {
+        let idle = !self.is_pending();
+        self.pending.extend(bunch);
 
-        let msgs_list = Paragraph::new(msgs)
-            .block(Block::default().borders(Borders::ALL))
-            .wrap(tui::widgets::Wrap { trim: true });
+        if idle {
+            self.show_next();
+        }
+    }
+
+    /// Check if a story sequence still needs the player to continue.
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty() || self.revealed.is_some()
+    }
+
+    /// Finish the message being typed, or show the next queued one.
+    pub fn advance(&mut self) {
+        if self.revealed.is_some() {
+            self.revealed = None;
+        } else {
+            self.show_next();
+        }
+    }
+
+    pub fn on_key(&mut self, code: char) {
+        if code == ' ' {
+            self.advance();
+        }
+    }
+
+    fn show_next(&mut self) {
+        if let Some(msg) = self.pending.pop_front() {
+            self.revealed = if self.typing_speed > 0 { Some(0) } else { None };
+            self.messages.push(msg);
+        }
+    }
+
+    /// The last message shown.
+    pub fn current(&self) -> Option<&Msg> {
+        self.messages.last()
+    }
+
+    /// Type more of the current message, which has `total` characters.
+    pub fn on_tick(&mut self, total: usize) {
+        if let Some(revealed) = self.revealed {
+            let revealed = revealed + self.typing_speed;
+            self.revealed = if revealed >= total {
+                None
+            } else {
+                Some(revealed)
+            };
+        }
+    }
+
+    pub fn draw<B: Backend>(&self, f: &mut Frame<B>, state: &GameState, area: Rect) {
+        let last = self.messages.len().saturating_sub(1);
+        let mut msgs: Vec<Spans<'static>> = self
+            .messages
+            .iter()
+            .enumerate()
+            .map(|(i, m)| match self.revealed {
+                Some(limit) if i == last => m.to_partial_spans(state, limit),
+                _ => m.to_spans(state),
+            })
+            .collect();
+
+        if self.is_pending() {
+            msgs.push(Spans::from(Span::styled(
+                tr("message.continue"),
+                MsgStyle::Hint.to_style(&state.theme),
+            )));
+        }
+
+        // wrap by display width and keep the latest lines in view
+        let block = Block::default().borders(Borders::ALL);
+        let inner = block.inner(area);
+        let lines: Vec<Spans> = msgs
+            .iter()
+            .flat_map(|m| text::wrap(m, inner.width as usize))
+            .collect();
+        let scroll = lines.len().saturating_sub(inner.height as usize) as u16;
+
+        let msgs_list = Paragraph::new(lines).block(block).scroll((scroll, 0));
 
         f.render_widget(msgs_list, area);
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum MsgStyle {
     Default,
     Hint,
     Target,
+    Npc,
+    Item,
+    Danger,
+    /// A style declared in the theme asset.
+    Custom(String),
 }
 
 impl MsgStyle {
-    pub fn to_style(&self) -> Style {
-        match *self {
+    /// Get the style of a markup tag name.
+    pub fn from_name(name: &str) -> MsgStyle {
+        match name {
+            "default" => MsgStyle::Default,
+            "hint" => MsgStyle::Hint,
+            "target" => MsgStyle::Target,
+            "npc" => MsgStyle::Npc,
+            "item" => MsgStyle::Item,
+            "danger" => MsgStyle::Danger,
+            _ => MsgStyle::Custom(name.to_string()),
+        }
+    }
+
+    pub fn to_style(&self, theme: &Theme) -> Style {
+        match self {
             MsgStyle::Default => Style::default(),
             MsgStyle::Hint => Style::default().fg(Color::Yellow),
             MsgStyle::Target => Style::default().fg(Color::Blue),
+            MsgStyle::Npc => Style::default().fg(Color::Cyan),
+            MsgStyle::Item => Style::default().fg(Color::Green),
+            MsgStyle::Danger => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            MsgStyle::Custom(name) => theme.get(name).unwrap_or_default(),
+        }
+    }
+}
+
+/// User defined message styles.
+#[derive(Default, Deserialize, Clone)]
+pub struct Theme {
+    styles: HashMap<String, StyleDef>,
+}
+
+impl Asset for Theme {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+impl Theme {
+    pub fn load(cache: &AssetCache) -> Theme {
+        let theme_file = "theme";
+        let handle = cache.load_expect::<Theme>(theme_file);
+
+        handle.read().to_owned()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Style> {
+        self.styles.get(name).map(|s| s.to_style())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.styles.contains_key(name)
+    }
+}
+
+#[derive(Deserialize, Clone)]
+struct StyleDef {
+    fg: Option<Color>,
+    bg: Option<Color>,
+    #[serde(default)]
+    modifiers: Vec<StyleModifier>,
+}
+
+impl StyleDef {
+    fn to_style(&self) -> Style {
+        let mut style = Style::default();
+        if let Some(fg) = self.fg {
+            style = style.fg(fg);
+        }
+        if let Some(bg) = self.bg {
+            style = style.bg(bg);
         }
+        self.modifiers
+            .iter()
+            .fold(style, |style, m| style.add_modifier(m.to_modifier()))
     }
 }
 
+#[derive(Deserialize, Clone, Copy)]
+enum StyleModifier {
+    Bold,
+    Dim,
+    Italic,
+    Underlined,
+    Reversed,
+}
+
+impl StyleModifier {
+    fn to_modifier(self) -> Modifier {
+        match self {
+            StyleModifier::Bold => Modifier::BOLD,
+            StyleModifier::Dim => Modifier::DIM,
+            StyleModifier::Italic => Modifier::ITALIC,
+            StyleModifier::Underlined => Modifier::UNDERLINED,
+            StyleModifier::Reversed => Modifier::REVERSED,
+        }
+    }
+}
+
+/// Text split into pieces with their own style.
+pub type StyledText = Vec<(String, MsgStyle)>;
+
+/// Message text written either as inline markup or as styled pairs.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawText {
+    Markup(String),
+    Pairs(StyledText),
+}
+
+impl RawText {
+    fn parse(self) -> Result<StyledText, String> {
+        match self {
+            RawText::Markup(text) => markup::parse(&text),
+            RawText::Pairs(pairs) => Ok(pairs),
+        }
+    }
+}
+
+/// Message contents, either a single text or one text per language.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawContents {
+    Text(RawText),
+    Localized(BTreeMap<String, RawText>),
+}
+
+fn deserialize_contents<'de, D>(deserializer: D) -> Result<BTreeMap<String, StyledText>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let texts = match RawContents::deserialize(deserializer)? {
+        RawContents::Text(text) => BTreeMap::from([(locale::FALLBACK_LANGUAGE.to_string(), text)]),
+        RawContents::Localized(texts) => texts,
+    };
+
+    texts
+        .into_iter()
+        .map(|(language, text)| Ok((language, text.parse()?)))
+        .collect::<Result<_, String>>()
+        .map_err(serde::de::Error::custom)
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Msg {
     msg_type: MsgType,
-    contents: Vec<(String, MsgStyle)>,
+    /// Contents keyed by language id.
+    #[serde(deserialize_with = "deserialize_contents")]
+    contents: BTreeMap<String, StyledText>,
 }
 
 impl Msg {
-    // pub fn contents(mut self, contents: Vec<Span<'a>>) -> Self {
-    //     self.contents = contents;
-    //     self
-    // }
+    /// Create a message written in the selected language.
+    pub fn new(msg_type: MsgType, contents: StyledText) -> Self {
+        Self {
+            msg_type,
+            contents: BTreeMap::from([(locale::language(), contents)]),
+        }
+    }
+
+    /// Create a message from inline markup. Invalid markup is shown as
+    /// plain text.
+    pub fn markup(msg_type: MsgType, text: &str) -> Self {
+        let contents =
+            markup::parse(text).unwrap_or_else(|_| vec![(text.to_string(), MsgStyle::Default)]);
+        Self::new(msg_type, contents)
+    }
 
-    pub fn to_spans(&self) -> Spans {
+    pub fn to_spans(&self, state: &GameState) -> Spans<'static> {
+        self.to_partial_spans(state, usize::MAX)
+    }
+
+    /// Convert to spans with at most `limit` characters of contents.
+    ///
+    /// Placeholders in the contents are resolved from the game state.
+    pub fn to_partial_spans(&self, state: &GameState, limit: usize) -> Spans<'static> {
         let mut output = vec![Span::styled(
             format!("{}:", self.msg_type.get_from()),
             self.msg_type.get_style(),
         )];
-        output.extend(
-            self.contents
-                .iter()
-                .map(|(raw, style)| Span::styled(raw, style.to_style())),
-        );
+        let mut left = limit;
+        for (raw, style) in self.contents() {
+            if left == 0 {
+                break;
+            }
+            let text = template::render(raw, state);
+            let count = text.chars().count();
+            if count <= left {
+                output.push(Span::styled(text, style.to_style(&state.theme)));
+                left -= count;
+            } else {
+                let cut: String = text.chars().take(left).collect();
+                output.push(Span::styled(cut, style.to_style(&state.theme)));
+                left = 0;
+            }
+        }
         Spans::from(output)
     }
+
+    /// Number of characters in the contents once placeholders are resolved.
+    pub fn char_count(&self, state: &GameState) -> usize {
+        self.contents()
+            .iter()
+            .map(|(raw, _)| template::render(raw, state).chars().count())
+            .sum()
+    }
+
+    /// Contents in the selected language.
+    ///
+    /// Fall back to the default language, then to any language the
+    /// message is written in.
+    pub fn contents(&self) -> &[(String, MsgStyle)] {
+        self.contents
+            .get(&locale::language())
+            .or_else(|| self.contents.get(locale::FALLBACK_LANGUAGE))
+            .or_else(|| self.contents.values().next())
+            .map_or(&[], |c| c.as_slice())
+    }
+
+    /// Contents in every language the message is written in.
+    pub fn all_contents(&self) -> impl Iterator<Item = (&str, &[(String, MsgStyle)])> {
+        self.contents
+            .iter()
+            .map(|(language, c)| (language.as_str(), c.as_slice()))
+    }
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -101,11 +415,11 @@ impl MsgType {
         }
     }
 
-    pub fn get_from(&self) -> &str {
-        match *self {
-            MsgType::System => "系统",
-            MsgType::Input => "我",
-            MsgType::Battle => "战斗",
-        }
+    pub fn get_from(&self) -> String {
+        tr(match *self {
+            MsgType::System => "msg.system",
+            MsgType::Input => "msg.input",
+            MsgType::Battle => "msg.battle",
+        })
     }
 }
@@ -1,11 +1,20 @@
+pub mod battle;
+pub mod crafting;
+pub mod creation;
 pub mod dashboard;
+pub mod inventory;
 pub mod map;
 pub mod message;
 pub mod player;
 pub mod saves;
+pub mod settings;
+pub mod sheet;
+pub mod shop;
+pub mod text;
 
 use core::fmt;
 
+use serde::{Deserialize, Serialize};
 use tui::{
     buffer::Buffer,
     layout::Rect,
@@ -13,12 +22,20 @@ use tui::{
     widgets::{Row, TableState, Widget},
 };
 
+use crate::game::locale::tr;
+
 pub enum Id {
+    Battle,
+    Crafting,
+    Creation,
     Dashboard,
     Map,
     SaveMenu,
     Dialogue,
+    Inventory,
     PlayerInfo,
+    Settings,
+    Shop,
 }
 
 pub enum Direction {
@@ -28,19 +45,27 @@ pub enum Direction {
     Down,
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct WorldState {
     pub clock: Clock,
 }
 
+/// Minutes in one hour (时辰) of the game clock.
+pub const MINUTES_PER_HOUR: u16 = 120;
+
+#[derive(Serialize, Deserialize)]
 pub struct Clock {
+    #[serde(default)]
+    pub day: u32,
     pub hour: Hour,
+    /// Minutes passed in the current hour.
     pub subs: u16,
 }
 
 impl Default for Clock {
     fn default() -> Self {
         Self {
+            day: 0,
             hour: Hour::Zi,
             subs: 0,
         }
@@ -53,15 +78,33 @@ impl fmt::Display for Clock {
             f,
             "{} {}",
             self.hour,
-            match self.subs {
-                0 => "初",
-                1 => "正",
-                _ => "",
+            if self.subs < MINUTES_PER_HOUR / 2 {
+                tr("clock.early")
+            } else {
+                tr("clock.late")
             }
         )
     }
-} // TODO apply time system
-#[allow(dead_code)]
+}
+
+impl Clock {
+    pub fn advance(&mut self, minutes: u32) {
+        let total = self.subs as u32 + minutes;
+        let hours = self.hour.index() as u32 + total / MINUTES_PER_HOUR as u32;
+
+        self.subs = (total % MINUTES_PER_HOUR as u32) as u16;
+        self.hour = Hour::ALL[(hours % 12) as usize];
+        self.day += hours / 12;
+    }
+
+    /// Minutes passed since the first day.
+    pub fn total_minutes(&self) -> u64 {
+        (self.day as u64 * 12 + self.hour.index() as u64) * MINUTES_PER_HOUR as u64
+            + self.subs as u64
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum Hour {
     Zi,
     Chou,
@@ -77,25 +120,51 @@ pub enum Hour {
     Hai,
 }
 
+impl Hour {
+    pub const ALL: [Hour; 12] = [
+        Hour::Zi,
+        Hour::Chou,
+        Hour::Yin,
+        Hour::Mao,
+        Hour::Chen,
+        Hour::Si,
+        Hour::Wu,
+        Hour::Mo,
+        Hour::Shen,
+        Hour::You,
+        Hour::Xu,
+        Hour::Hai,
+    ];
+
+    pub fn index(&self) -> usize {
+        Hour::ALL.iter().position(|h| h == self).unwrap_or(0)
+    }
+
+    /// From 亥时 to 寅时.
+    pub fn is_night(&self) -> bool {
+        matches!(self, Hour::Hai | Hour::Zi | Hour::Chou | Hour::Yin)
+    }
+}
+
 impl fmt::Display for Hour {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
             "{}",
-            match &self {
-                Hour::Zi => "子",
-                Hour::Chou => "丑",
-                Hour::Yin => "寅",
-                Hour::Mao => "卯",
-                Hour::Chen => "辰",
-                Hour::Si => "巳",
-                Hour::Wu => "午",
-                Hour::Mo => "未",
-                Hour::Shen => "申",
-                Hour::You => "酉",
-                Hour::Xu => "戌",
-                Hour::Hai => "亥",
-            }
+            tr(match &self {
+                Hour::Zi => "hour.zi",
+                Hour::Chou => "hour.chou",
+                Hour::Yin => "hour.yin",
+                Hour::Mao => "hour.mao",
+                Hour::Chen => "hour.chen",
+                Hour::Si => "hour.si",
+                Hour::Wu => "hour.wu",
+                Hour::Mo => "hour.mo",
+                Hour::Shen => "hour.shen",
+                Hour::You => "hour.you",
+                Hour::Xu => "hour.xu",
+                Hour::Hai => "hour.hai",
+            })
         )
     }
 }
@@ -109,7 +178,8 @@ struct Menu {
 impl Widget for Menu {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let width = area.width;
-        let menu_str_len = self.items.iter().fold(0, |sum, i| sum + i.chars().count()) as u16; // "< item >"
+        let labels: Vec<String> = self.items.iter().map(|i| format!("< {} >", i)).collect();
+        let menu_str_len = labels.iter().map(|l| text::width(l)).sum::<usize>() as u16;
         let spacer = if width >= menu_str_len {
             (width - menu_str_len) / (self.items.len() as u16 + 1)
         } else {
@@ -123,7 +193,7 @@ impl Widget for Menu {
 
         let mut x = area.left();
         x += spacer;
-        for (idx, item) in self.items.iter().enumerate() {
+        for (idx, label) in labels.iter().enumerate() {
             let style = match self.selected {
                 None => normal_style,
                 Some(i) => {
@@ -134,13 +204,14 @@ impl Widget for Menu {
                     }
                 }
             };
-            buf.set_string(x, area.top(), format!("< {} >", item), style);
-            x += spacer;
+            buf.set_string(x, area.top(), label, style);
+            x += text::width(label) as u16 + spacer;
         }
     }
 }
 
 impl Menu {
+    /// Create a menu whose items are locale keys.
     fn with_items(items: Vec<&str>) -> Menu {
         Menu {
             selected: if items.is_empty() { None } else { Some(0) },
@@ -148,6 +219,14 @@ impl Menu {
         }
     }
 
+    /// Copy of the menu with items translated to the selected language.
+    fn localized(&self) -> Menu {
+        Menu {
+            selected: self.selected,
+            items: self.items.iter().map(|i| tr(i)).collect(),
+        }
+    }
+
     fn next(&mut self) {
         let i = match self.selected {
             Some(i) => {
@@ -233,7 +312,52 @@ impl<T: TableData> StatefulTable<T> {
 }
 
 trait TableData {
-    fn header(&self) -> Row;
-    fn data(&self) -> Vec<Row>;
+    fn header(&self) -> Row<'_>;
+    fn data(&self) -> Vec<Row<'_>>;
     fn len(&self) -> usize;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Day, hour index and minutes of a clock.
+    fn time(clock: &Clock) -> (u32, usize, u16) {
+        (clock.day, clock.hour.index(), clock.subs)
+    }
+
+    #[test]
+    fn advance_within_hour() {
+        let mut clock = Clock::default();
+        clock.advance(119);
+        assert_eq!(time(&clock), (0, 0, 119));
+        assert_eq!(clock.total_minutes(), 119);
+    }
+
+    #[test]
+    fn advance_rolls_over_hours() {
+        let mut clock = Clock::default();
+        clock.advance(100);
+        clock.advance(30);
+        assert_eq!(time(&clock), (0, 1, 10));
+        clock.advance(MINUTES_PER_HOUR as u32 * 3);
+        assert_eq!(time(&clock), (0, 4, 10));
+        assert_eq!(clock.total_minutes(), 4 * 120 + 10);
+    }
+
+    #[test]
+    fn advance_rolls_over_days() {
+        let mut clock = Clock {
+            day: 2,
+            hour: Hour::Hai,
+            subs: 110,
+        };
+        clock.advance(20);
+        assert_eq!(time(&clock), (3, 0, 10));
+
+        // more than a day at once
+        clock.advance(12 * 120 * 2 + 120);
+        assert_eq!(time(&clock), (5, 1, 10));
+        assert_eq!(clock.total_minutes(), (5 * 12 + 1) * 120 + 10);
+    }
+}
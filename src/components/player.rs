@@ -1,42 +1,668 @@
+use std::cmp::Ordering;
+
 use serde::{Deserialize, Serialize};
 use tui::{
     backend::Backend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
-    widgets::{Block, Borders, Gauge, Paragraph},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Cell, Gauge, LineGauge, Paragraph, Row, Table, TableState},
     Frame,
 };
 
-use crate::game::{Attribute, Character, Pos, Property, PropertyType, PropertyValue};
+use crate::game::{
+    apply_modifiers, carry_limit,
+    locale::{tr, trf},
+    ActiveBuff, Activity, Attribute, AttributeType, Buff, Buffs, Character, EquipSlot, Equipment,
+    Injuries, InjuryChange, InjuryKind, InjuryRule, Inventory, Item, Levels, ModTarget, Pos,
+    Property, PropertyType, Regen, Reputation, Rules, Skill, SkillKind, Skills,
+};
+
+use super::MINUTES_PER_HOUR;
 
 #[derive(Default, Serialize, Deserialize)]
 pub struct Player {
     name: String,
     pos: Pos,
+    #[serde(default = "default_level")]
+    level: u32,
+    /// Total experience gained.
+    #[serde(default)]
+    exp: u32,
+    /// Attribute points left to spend.
+    #[serde(default)]
+    points: u32,
+    /// Inner energy cultivated by meditation.
+    #[serde(default)]
+    neili: f64,
+    /// Total experience to reach the next level, None at the top level.
+    #[serde(skip)]
+    next_exp: Option<u32>,
 
+    /// Base attributes before any modifier.
     attr: Attribute,
+    /// Attributes with all modifiers applied, kept up to date by
+    /// [`Player::recompute`].
+    #[serde(skip)]
+    effective: Attribute,
     prop: Property,
-    // TODO Add a buff field to show changes in attr and prop
+    #[serde(default)]
+    buffs: Buffs,
+    #[serde(default)]
+    skills: Skills,
+    #[serde(default)]
+    inventory: Inventory,
+    #[serde(default)]
+    equipment: Equipment,
+    #[serde(default)]
+    injuries: Injuries,
+    #[serde(default)]
+    reputation: Reputation,
+    /// Formula errors met while recomputing, waiting to be reported.
+    #[serde(skip)]
+    formula_errors: Vec<String>,
+}
+
+fn default_level() -> u32 {
+    1
 }
 
 impl Player {
-    pub fn init() -> Self {
+    /// Create a new character at the start of the game.
+    pub fn new(name: &str, attr: Attribute) -> Self {
         Self {
-            name: "方洵".to_string(),
+            name: name.to_string(),
             pos: Pos::new(3, 0),
-            attr: Attribute::human(),
-            prop: Property::from_attrs(Attribute::human()),
+            level: default_level(),
+            exp: 0,
+            points: 0,
+            neili: 0.0,
+            next_exp: None,
+            effective: attr.clone(),
+            attr,
+            prop: Property::default(),
+            buffs: Buffs::default(),
+            skills: Skills::default(),
+            inventory: Inventory::default(),
+            equipment: Equipment::default(),
+            injuries: Injuries::default(),
+            reputation: Reputation::default(),
+            formula_errors: vec![],
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    pub fn points(&self) -> u32 {
+        self.points
+    }
+
+    /// Base attributes before any modifier.
+    pub fn attr(&self) -> &Attribute {
+        &self.attr
+    }
+
+    pub fn prop(&self) -> &Property {
+        &self.prop
+    }
+
+    pub fn buffs(&self) -> &Buffs {
+        &self.buffs
+    }
+
+    pub fn skills(&self) -> &Skills {
+        &self.skills
+    }
+
+    pub fn inventory(&self) -> &Inventory {
+        &self.inventory
+    }
+
+    pub fn equipment(&self) -> &Equipment {
+        &self.equipment
+    }
+
+    pub fn injuries(&self) -> &Injuries {
+        &self.injuries
+    }
+
+    pub fn reputation(&self) -> &Reputation {
+        &self.reputation
+    }
+
+    pub fn reputation_mut(&mut self) -> &mut Reputation {
+        &mut self.reputation
+    }
+
+    /// Equip an item from an inventory slot. The item worn before goes
+    /// back to the inventory. Return the equipped item, or None if it
+    /// cannot be equipped or the inventory has no room for the old one.
+    pub fn equip<'a>(&mut self, index: usize, rules: &'a Rules) -> Option<&'a Item> {
+        let item = rules.items.get(&self.inventory.get(index)?.item)?;
+        let slot = EquipSlot::of(item.category)?;
+
+        self.inventory.remove(&item.id, 1);
+        if let Some(old) = self.equipment.equip(slot, item.id.clone()) {
+            // put everything back if there is no room for the old item
+            let put = rules
+                .items
+                .get(&old)
+                .map_or(0, |old| self.inventory.add(old, 1, f64::INFINITY));
+            if put == 0 {
+                self.equipment.equip(slot, old);
+                self.inventory.add(item, 1, f64::INFINITY);
+                return None;
+            }
+        }
+        self.recompute(rules);
+        Some(item)
+    }
+
+    /// Take off an item into the inventory. Return the item, or None if
+    /// nothing is worn there or the inventory is full.
+    pub fn unequip<'a>(&mut self, slot: EquipSlot, rules: &'a Rules) -> Option<&'a Item> {
+        let item = rules.items.get(self.equipment.get(slot)?)?;
+        if self.inventory.add(item, 1, f64::INFINITY) == 0 {
+            return None;
+        }
+
+        self.equipment.unequip(slot);
+        self.recompute(rules);
+        Some(item)
+    }
+
+    /// Level up as long as the experience reaches the next level. Return
+    /// the number of levels gained.
+    pub fn update_level(&mut self, levels: &Levels, rules: &Rules) -> u32 {
+        let mut gained = 0;
+        while levels.next(self.level).is_some_and(|n| self.exp >= n) {
+            self.level += 1;
+            self.points += levels.points();
+            gained += 1;
+        }
+        self.next_exp = levels.next(self.level);
+
+        if gained > 0 {
+            self.recompute(rules);
+        }
+        gained
+    }
+
+    /// Lose a part of the experience gained since the last level. Levels
+    /// are never lost. Return the experience lost.
+    pub fn lose_exp(&mut self, ratio: f64, levels: &Levels) -> u32 {
+        let start = levels.next(self.level.saturating_sub(1)).unwrap_or(0);
+        let lost = (self.exp.saturating_sub(start) as f64 * ratio).floor() as u32;
+        self.exp -= lost;
+        lost
+    }
+
+    /// Gain experience. Return the number of levels gained.
+    pub fn gain_exp(&mut self, amount: u32, levels: &Levels, rules: &Rules) -> u32 {
+        self.exp = self.exp.saturating_add(amount);
+        self.update_level(levels, rules)
+    }
+
+    /// Spend an attribute point on a base attribute. Return false if no
+    /// point is left.
+    pub fn raise_attr(&mut self, attr: AttributeType, rules: &Rules) -> bool {
+        if self.points == 0 {
+            return false;
+        }
+
+        self.points -= 1;
+        self.attr.set(attr, self.attr.get(attr) + 1);
+        self.recompute(rules);
+        true
+    }
+
+    /// Weight the player can carry.
+    pub fn max_weight(&self) -> f64 {
+        carry_limit(self.effective_attr().get(AttributeType::Str))
+    }
+
+    /// Put items in the inventory. Return the number of items that fit.
+    pub fn give_item(&mut self, item: &Item, count: u32, rules: &Rules) -> u32 {
+        let room = self.max_weight() - self.inventory.weight(&rules.items);
+        self.inventory.add(item, count, room)
+    }
+
+    /// Take items from the inventory. Return the number of items taken.
+    pub fn take_item(&mut self, id: &str, count: u32) -> u32 {
+        self.inventory.remove(id, count)
+    }
+
+    /// Throw away a whole inventory slot. Return the id of its items.
+    pub fn drop_slot(&mut self, index: usize) -> Option<String> {
+        self.inventory.remove_slot(index).map(|s| s.item)
+    }
+
+    /// Modifiers of buffs, learned skills, equipment and injuries, with
+    /// their stacks, levels or severity.
+    fn modifiers<'a>(
+        &'a self,
+        rules: &'a Rules,
+    ) -> impl Iterator<Item = (&'a crate::game::Modifier, u32)> + Clone {
+        self.buffs
+            .modifiers(&rules.buffs)
+            .chain(self.skills.modifiers(&rules.skills))
+            .chain(self.equipment.modifiers(&rules.items))
+            .chain(self.injuries.modifiers(&rules.injuries))
+    }
+
+    /// Attributes with all modifiers applied.
+    pub fn effective_attr(&self) -> Attribute {
+        self.effective.clone()
+    }
+
+    /// Value of a variable used in formulas.
+    ///
+    /// Attribute names such as `con` give effective attributes, `base.con`
+    /// gives base attributes, `level` the level, `buff.<id>` the stacks
+    /// of a buff, `skill.<id>` the level of a skill and `inner` the
+    /// levels of all inner arts.
+    fn formula_var(&self, rules: &Rules, name: &str) -> Option<f64> {
+        if name == "level" {
+            Some(self.level as f64)
+        } else if name == "inner" {
+            Some(self.skills.total_level(SkillKind::InnerArt, &rules.skills) as f64)
+        } else if let Some(id) = name.strip_prefix("buff.") {
+            Some(self.buffs.stacks(id) as f64)
+        } else if let Some(id) = name.strip_prefix("skill.") {
+            Some(self.skills.level(id) as f64)
+        } else if let Some(key) = name.strip_prefix("base.") {
+            AttributeType::from_key(key).map(|t| self.attr.get(t) as f64)
+        } else {
+            AttributeType::from_key(name).map(|t| self.effective.get(t) as f64)
+        }
+    }
+
+    /// Value of the formula of a maximum, before modifiers.
+    fn formula_max(&self, rules: &Rules, prop: PropertyType) -> Result<f64, String> {
+        let formula = rules
+            .formulas
+            .get(&prop)
+            .ok_or_else(|| format!("{:?}: no formula", prop))?;
+        formula
+            .eval(&|name| self.formula_var(rules, name))
+            .map_err(|e| format!("{:?}: {} in `{}`", prop, e, formula))
+    }
+
+    /// Recompute effective attributes, and maximum properties from
+    /// formulas and modifiers. Current values are kept within the new
+    /// maxima.
+    ///
+    /// A maximum whose formula fails keeps its previous value, and the
+    /// error waits in [`Player::take_formula_errors`].
+    ///
+    /// The formula of MaxNeili is the most inner energy the learned inner
+    /// arts can hold. Only what has been cultivated of it counts.
+    pub fn recompute(&mut self, rules: &Rules) {
+        let mut attr = self.attr.clone();
+        for t in AttributeType::ALL {
+            let value = apply_modifiers(
+                self.attr.get(t) as f64,
+                ModTarget::Attr(t),
+                self.modifiers(rules),
+            );
+            attr.set(t, value.round().max(0.0) as usize);
+        }
+        self.effective = attr;
+
+        for cur in PropertyType::CURRENT {
+            let max = match cur.max() {
+                Some(max) => max,
+                None => continue,
+            };
+            let mut base = match self.formula_max(rules, max) {
+                Ok(base) => base,
+                Err(e) => {
+                    self.formula_error(e);
+                    continue;
+                }
+            };
+            if max == PropertyType::MaxNeili {
+                base = base.min(self.neili);
+            }
+            let value = apply_modifiers(base, ModTarget::Prop(max), self.modifiers(rules))
+                .round()
+                .max(0.0);
+            self.prop.set_max(cur, value);
+        }
+    }
+
+    /// Keep a formula error to report, once.
+    fn formula_error(&mut self, error: String) {
+        if !self.formula_errors.contains(&error) {
+            self.formula_errors.push(error);
+        }
+    }
+
+    /// Formula errors met since the last call.
+    pub fn take_formula_errors(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.formula_errors)
+    }
+
+    pub fn add_buff(&mut self, buff: &Buff, source: &str, rules: &Rules) {
+        self.buffs.apply(buff, source);
+        self.recompute(rules);
+    }
+
+    pub fn remove_buff(&mut self, id: &str, rules: &Rules) {
+        if self.buffs.remove(id) {
+            self.recompute(rules);
+        }
+    }
+
+    /// Suffer an injury. Return its new severity.
+    pub fn injure(&mut self, rule: &InjuryRule, severity: u32, rules: &Rules) -> u32 {
+        let severity = self.injuries.inflict(rule, severity);
+        self.recompute(rules);
+        severity
+    }
+
+    /// Treat an injury. Return the severity left, or None if the player
+    /// does not suffer from it.
+    pub fn treat(&mut self, kind: InjuryKind, amount: u32, rules: &Rules) -> Option<u32> {
+        let left = self.injuries.treat(kind, amount);
+        if left.is_some() {
+            self.recompute(rules);
+        }
+        left
+    }
+
+    /// Let injuries worsen or heal over some minutes of an activity.
+    /// Return how they changed.
+    pub fn tend_injuries(
+        &mut self,
+        minutes: u32,
+        activity: Activity,
+        rules: &Rules,
+    ) -> Vec<(InjuryKind, InjuryChange)> {
+        let changes = self
+            .injuries
+            .pass_minutes(minutes, activity, &rules.injuries);
+        if !changes.is_empty() {
+            self.recompute(rules);
+        }
+        changes
+    }
+
+    /// Learn a skill. Return false if it is already learned.
+    pub fn learn_skill(&mut self, skill: &Skill, rules: &Rules) -> bool {
+        let learned = self.skills.learn(skill);
+        if learned {
+            self.recompute(rules);
+        }
+        learned
+    }
+
+    /// Practice a skill. Return the number of levels gained.
+    pub fn practice_skill(&mut self, id: &str, amount: u32, rules: &Rules) -> u32 {
+        let gained = match rules.skills.get(id) {
+            Some(skill) => self.skills.practice(skill, amount),
+            None => 0,
+        };
+        if gained > 0 {
+            self.recompute(rules);
+        }
+        gained
+    }
+
+    /// Change a current property, keeping it between 0 and its maximum.
+    pub fn change_prop(&mut self, prop: PropertyType, delta: f64) {
+        self.prop.change(prop, delta);
+    }
+
+    /// Regenerate current properties over some minutes of an activity.
+    /// Growth of MaxNeili cultivates inner energy, up to what the inner
+    /// arts can hold.
+    pub fn regenerate(
+        &mut self,
+        regen: &Regen,
+        rules: &Rules,
+        activity: Activity,
+        night: bool,
+        minutes: u32,
+    ) {
+        let amounts: Vec<(PropertyType, f64)> = PropertyType::CURRENT
+            .into_iter()
+            .chain([PropertyType::MaxNeili])
+            .filter_map(|prop| {
+                let per_hour = regen.get(activity, &prop)?.eval(&|name| match name {
+                    "night" => Some(if night { 1.0 } else { 0.0 }),
+                    _ => self.formula_var(rules, name),
+                });
+                per_hour
+                    .ok()
+                    .map(|v| (prop, v * minutes as f64 / MINUTES_PER_HOUR as f64))
+            })
+            .collect();
+
+        for (prop, amount) in amounts {
+            if prop == PropertyType::MaxNeili {
+                match self.formula_max(rules, prop) {
+                    Ok(limit) => self.neili = (self.neili + amount).min(limit).max(self.neili),
+                    Err(e) => self.formula_error(e),
+                }
+                self.recompute(rules);
+            } else {
+                self.prop.change(prop, amount);
+            }
+        }
+    }
+
+    /// Beaten when Jing runs out.
+    pub fn is_beaten(&self) -> bool {
+        self.prop.number(&PropertyType::Jing) <= 0.0
+    }
+
+    /// Bring Jing, Qi and Shen up to a part of their maxima.
+    pub fn revive(&mut self, ratio: f64) {
+        for prop in [PropertyType::Jing, PropertyType::Qi, PropertyType::Shen] {
+            if let Some(max) = prop.max() {
+                let least = (self.prop.number(&max) * ratio).ceil().max(1.0);
+                let delta = least - self.prop.number(&prop);
+                if delta > 0.0 {
+                    self.prop.change(prop, delta);
+                }
+            }
+        }
+    }
+
+    /// Move to a place on the map.
+    pub fn set_pos(&mut self, (x, y): (usize, usize)) {
+        self.pos = Pos::new(x, y);
+    }
+
+    /// Whether Jing, Qi and Shen are all at their maxima.
+    pub fn is_recovered(&self) -> bool {
+        [PropertyType::Jing, PropertyType::Qi, PropertyType::Shen]
+            .iter()
+            .all(|p| {
+                p.max()
+                    .is_some_and(|m| self.prop.number(p) >= self.prop.number(&m))
+            })
+    }
+
+    /// Let game time pass. Return the buffs that wore off.
+    pub fn pass_minutes(&mut self, minutes: u32, rules: &Rules) -> Vec<ActiveBuff> {
+        let expired = self.buffs.pass_minutes(minutes);
+        if !expired.is_empty() {
+            self.recompute(rules);
+        }
+        expired
+    }
+
+    /// End a turn. Return the buffs that wore off.
+    pub fn pass_turn(&mut self, rules: &Rules) -> Vec<ActiveBuff> {
+        let expired = self.buffs.pass_turn();
+        if !expired.is_empty() {
+            self.recompute(rules);
+        }
+        expired
+    }
+
+    /// Experience and what the next level needs.
+    fn exp_text(&self) -> String {
+        match self.next_exp {
+            Some(next) => trf("sheet.exp", &[&self.exp.to_string(), &next.to_string()]),
+            None => trf("sheet.exp_top", &[&self.exp.to_string()]),
+        }
+    }
+
+    fn gauge(&self, cur: PropertyType, max: PropertyType, color: Color) -> Gauge<'_> {
+        let (cur_value, max_value) = (self.prop.number(&cur), self.prop.number(&max));
+        let ratio = if max_value > 0.0 {
+            (cur_value / max_value).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        Gauge::default()
+            .block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .title(cur.to_string())
+                    .title_alignment(Alignment::Center),
+            )
+            .label(format!(
+                "{cur}/{max}",
+                cur = cur_value.floor(),
+                max = max_value
+            ))
+            .gauge_style(Style::default().fg(color).bg(Color::Black))
+            .ratio(ratio)
+    }
+
+    /// Draw level, attributes and properties. An attribute row can be
+    /// selected to spend points on.
+    pub fn draw_details<B: Backend>(&self, f: &mut Frame<B>, area: Rect, selected: Option<usize>) {
+        let chunks = Layout::default()
+            .constraints(
+                [
+                    Constraint::Length(3),
+                    Constraint::Length(7),
+                    Constraint::Min(5),
+                ]
+                .as_ref(),
+            )
+            .split(area);
+
+        // draw name and level
+        {
+            let info = Spans::from(vec![
+                Span::styled(
+                    self.name.as_str(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(format!(
+                    "  {}",
+                    trf("sheet.level", &[&self.level.to_string()])
+                )),
+            ]);
+            let mut progress = vec![Span::raw(self.exp_text())];
+            if self.points > 0 {
+                progress.push(Span::styled(
+                    format!("  {}", trf("sheet.points", &[&self.points.to_string()])),
+                    Style::default().fg(Color::Green),
+                ));
+            }
+            f.render_widget(Paragraph::new(vec![info, Spans::from(progress)]), chunks[0]);
+        }
+
+        // draw base and effective attributes
+        {
+            let effective = self.effective_attr();
+            let rows: Vec<Row> = AttributeType::ALL
+                .iter()
+                .map(|t| {
+                    let (base, value) = (self.attr.get(*t), effective.get(*t));
+                    let style = match value.cmp(&base) {
+                        Ordering::Greater => Style::default().fg(Color::Green),
+                        Ordering::Less => Style::default().fg(Color::Red),
+                        Ordering::Equal => Style::default(),
+                    };
+                    Row::new(vec![
+                        Cell::from(t.to_string()),
+                        Cell::from(base.to_string()),
+                        Cell::from(value.to_string()).style(style),
+                    ])
+                })
+                .collect();
+            let table = Table::new(rows)
+                .header(
+                    Row::new(vec![
+                        tr("sheet.attr"),
+                        tr("sheet.base"),
+                        tr("sheet.effective"),
+                    ])
+                    .style(Style::default().fg(Color::DarkGray)),
+                )
+                .widths(&[
+                    Constraint::Length(16),
+                    Constraint::Length(8),
+                    Constraint::Length(8),
+                ])
+                .highlight_style(
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .highlight_symbol("> ");
+            let mut table_state = TableState::default();
+            table_state.select(selected);
+            f.render_stateful_widget(table, chunks[1], &mut table_state);
+        }
+
+        // draw current and maximum properties
+        {
+            let rows: Vec<Row> = [
+                (PropertyType::Jing, PropertyType::MaxJing, Color::Red),
+                (PropertyType::Qi, PropertyType::MaxQi, Color::Blue),
+                (PropertyType::Shen, PropertyType::MaxShen, Color::Yellow),
+                (PropertyType::Neili, PropertyType::MaxNeili, Color::Cyan),
+            ]
+            .into_iter()
+            .map(|(cur, max, color)| {
+                Row::new(vec![
+                    Cell::from(cur.to_string()).style(Style::default().fg(color)),
+                    Cell::from(self.prop.number(&cur).floor().to_string()),
+                    Cell::from(self.prop.number(&max).to_string()),
+                ])
+            })
+            .collect();
+            let table = Table::new(rows)
+                .header(
+                    Row::new(vec![tr("sheet.prop"), tr("sheet.current"), tr("sheet.max")])
+                        .style(Style::default().fg(Color::DarkGray)),
+                )
+                .widths(&[
+                    Constraint::Length(16),
+                    Constraint::Length(8),
+                    Constraint::Length(8),
+                ]);
+            f.render_widget(table, chunks[2]);
         }
     }
 }
 
 impl Character for Player {
-    fn draw_long_desc<B: Backend>(&self, _f: &mut Frame<B>, _area: Rect) {}
+    fn draw_long_desc<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        self.draw_details(f, area, None);
+    }
 
-    fn draw_short_desc<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+    fn draw_short_desc<B: Backend>(&self, f: &mut Frame<B>, area: Rect, rules: &Rules) {
         let chunks = Layout::default()
             .constraints(
                 [
+                    Constraint::Length(1),
                     Constraint::Length(1),
                     Constraint::Length(2),
                     Constraint::Min(1),
@@ -53,83 +679,78 @@ impl Character for Player {
 
         // draw name, title, and level
         {
-            let info = Paragraph::new(self.name.as_str()).alignment(Alignment::Center);
+            let info = Paragraph::new(format!(
+                "{}  {}",
+                self.name,
+                trf("sheet.level", &[&self.level.to_string()])
+            ))
+            .alignment(Alignment::Center);
             f.render_widget(info, chunks[0]);
         }
 
-        // draw Jing, Qi, and Shen
+        // draw progress toward the next level
+        {
+            let ratio = match self.next_exp {
+                Some(next) if next > 0 => (self.exp as f64 / next as f64).clamp(0.0, 1.0),
+                _ => 1.0,
+            };
+            let exp = LineGauge::default()
+                .label(self.exp_text())
+                .gauge_style(Style::default().fg(Color::Green))
+                .ratio(ratio);
+            f.render_widget(exp, chunks[1]);
+        }
+
+        // draw Jing, Qi, Shen and Neili
         {
             let chunks = Layout::default()
                 .constraints(
                     [
-                        Constraint::Ratio(1, 3),
-                        Constraint::Ratio(1, 3),
-                        Constraint::Ratio(1, 3),
+                        Constraint::Ratio(1, 4),
+                        Constraint::Ratio(1, 4),
+                        Constraint::Ratio(1, 4),
+                        Constraint::Ratio(1, 4),
                     ]
                     .as_ref(),
                 )
                 .direction(Direction::Horizontal)
-                .split(chunks[1]);
-
-            let (cur_jing, cur_qi, cur_shen) = (
-                self.prop
-                    .get(&PropertyType::Jing)
-                    .unwrap_or(&PropertyValue::Number(0.0)),
-                self.prop
-                    .get(&PropertyType::Qi)
-                    .unwrap_or(&PropertyValue::Number(0.0)),
-                self.prop
-                    .get(&PropertyType::Shen)
-                    .unwrap_or(&PropertyValue::Number(0.0)),
-            );
-            let (max_jing, max_qi, max_shen) = (
-                self.prop
-                    .get(&PropertyType::MaxJing)
-                    .unwrap_or(&PropertyValue::Number(0.0)),
-                self.prop
-                    .get(&PropertyType::MaxQi)
-                    .unwrap_or(&PropertyValue::Number(0.0)),
-                self.prop
-                    .get(&PropertyType::MaxShen)
-                    .unwrap_or(&PropertyValue::Number(0.0)),
-            );
+                .split(chunks[2]);
 
-            let (jing_gauge, qi_gauge, shen_gauge) = (
-                Gauge::default()
-                    .block(
-                        Block::default()
-                            .borders(Borders::TOP)
-                            .title("精")
-                            .title_alignment(Alignment::Center),
-                    )
-                    .label(format!("{cur}/{max}", cur = cur_jing, max = max_jing))
-                    .gauge_style(Style::default().fg(Color::Red).bg(Color::Black))
-                    .ratio(max_jing.unwrap_number() / cur_jing.unwrap_number()),
-                Gauge::default()
-                    .block(
-                        Block::default()
-                            .borders(Borders::TOP)
-                            .title("气")
-                            .title_alignment(Alignment::Center),
-                    )
-                    .label(format!("{cur}/{max}", cur = cur_qi, max = max_qi))
-                    .gauge_style(Style::default().fg(Color::Blue).bg(Color::Black))
-                    .ratio(max_qi.unwrap_number() / cur_qi.unwrap_number()),
-                Gauge::default()
-                    .block(
-                        Block::default()
-                            .borders(Borders::TOP)
-                            .title("神")
-                            .title_alignment(Alignment::Center),
-                    )
-                    .gauge_style(Style::default().fg(Color::Yellow).bg(Color::Black))
-                    .label(format!("{cur}/{max}", cur = cur_shen, max = max_shen))
-                    .ratio(max_shen.unwrap_number() / cur_shen.unwrap_number()),
+            let (jing_gauge, qi_gauge, shen_gauge, neili_gauge) = (
+                self.gauge(PropertyType::Jing, PropertyType::MaxJing, Color::Red),
+                self.gauge(PropertyType::Qi, PropertyType::MaxQi, Color::Blue),
+                self.gauge(PropertyType::Shen, PropertyType::MaxShen, Color::Yellow),
+                self.gauge(PropertyType::Neili, PropertyType::MaxNeili, Color::Cyan),
             );
 
             f.render_widget(jing_gauge, chunks[0]);
             f.render_widget(qi_gauge, chunks[1]);
             f.render_widget(shen_gauge, chunks[2]);
+            f.render_widget(neili_gauge, chunks[3]);
+        }
+
+        // draw injuries and active buffs
+        {
+            let injuries = self.injuries.iter().map(|i| {
+                Spans::from(vec![
+                    Span::styled(i.kind.to_string(), Style::default().fg(Color::Red)),
+                    Span::raw(format!(" {}", i.severity)),
+                ])
+            });
+            let lines: Vec<Spans> = injuries
+                .chain(self.buffs.iter().map(|b| {
+                    Spans::from(vec![
+                        Span::styled(b.name(&rules.buffs), Style::default().fg(Color::Magenta)),
+                        Span::raw(format!(" ({})", b.remaining.describe())),
+                    ])
+                }))
+                .collect();
+            let buffs = Paragraph::new(lines).block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .title(tr("buff.title")),
+            );
+            f.render_widget(buffs, chunks[3]);
         }
     }
 
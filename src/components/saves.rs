@@ -1,6 +1,6 @@
 use std::{
     fs::{self, DirEntry},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 use chrono::{DateTime, Utc};
@@ -12,6 +12,8 @@ use tui::{
     Frame,
 };
 
+use crate::game::locale::tr;
+
 use super::{StatefulTable, TableData};
 
 #[derive(Default)]
@@ -32,20 +34,19 @@ impl SaveData {
 }
 
 impl TableData for SaveData {
-    fn header(&self) -> Row {
-        Row::new(vec!["名称", "日期"])
+    fn header(&self) -> Row<'_> {
+        Row::new(vec![tr("saves.name"), tr("saves.date")])
     }
 
-    fn data(&self) -> Vec<Row> {
+    fn data(&self) -> Vec<Row<'_>> {
         let mut rows = Vec::new();
         for save in self.entry.iter() {
             let name = save
                 .file_name()
                 .to_str()
                 .unwrap()
-                .to_string()
                 .split('-')
-                .nth(0)
+                .next()
                 .unwrap()
                 .to_string();
             let date: DateTime<Utc> = save
@@ -71,38 +72,63 @@ impl TableData for SaveData {
 
 pub struct SaveMenu {
     list: StatefulTable<SaveData>,
+    /// Why the last chosen save could not be loaded.
+    problem: Option<String>,
 }
 
 impl Default for SaveMenu {
     fn default() -> Self {
         Self {
             list: StatefulTable::new(SaveData::default()),
+            problem: None,
         }
     }
 }
 
 impl SaveMenu {
     pub fn new(save_dir: &Path) -> Self {
-        let data: Vec<DirEntry> = fs::read_dir(save_dir)
-            .expect("Cannot read save directory")
-            .map(|entry| entry.expect("Cannot read save file"))
-            .collect();
+        // there are no saves before the first game
+        let data: Vec<DirEntry> = match fs::read_dir(save_dir) {
+            Ok(dir) => dir
+                .map(|entry| entry.expect("Cannot read save file"))
+                .collect(),
+            Err(_) => vec![],
+        };
         let mut save_data = SaveData { entry: data };
         save_data.sort();
 
         Self {
             list: StatefulTable::new(save_data),
+            problem: None,
         }
     }
 
+    /// Path of the selected save.
+    pub fn selected(&self) -> Option<PathBuf> {
+        self.list
+            .state
+            .selected()
+            .and_then(|i| self.list.items.entry.get(i))
+            .map(|entry| entry.path())
+    }
+
+    /// Tell the player why the chosen save could not be loaded.
+    pub fn report(&mut self, problem: String) {
+        self.problem = Some(problem);
+    }
+
     pub fn view<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
         let header = self.list.items.header();
         let data = self.list.items.data();
+        let title = match &self.problem {
+            Some(problem) => format!("{} - {}", tr("saves.title"), problem),
+            None => tr("saves.title"),
+        };
 
         let saves_table = Table::new(data)
             .header(header)
-            .block(Block::default().borders(Borders::ALL).title("存档"))
-            .widths(&[Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)].as_ref())
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .widths(&[Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
             .highlight_style(
                 Style::default()
                     .fg(Color::Green)
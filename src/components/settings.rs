@@ -0,0 +1,122 @@
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Row, Table, TableState},
+    Frame,
+};
+
+use crate::game::{
+    locale::{self, tr},
+    settings::Settings,
+};
+
+/// Entries shown in the settings menu.
+enum SettingItem {
+    Language,
+    Permadeath,
+}
+
+const ITEMS: [SettingItem; 2] = [SettingItem::Language, SettingItem::Permadeath];
+
+impl SettingItem {
+    fn label(&self) -> String {
+        match self {
+            SettingItem::Language => tr("settings.language"),
+            SettingItem::Permadeath => tr("settings.permadeath"),
+        }
+    }
+
+    fn value(&self, settings: &Settings) -> String {
+        match self {
+            SettingItem::Language => locale::languages()
+                .into_iter()
+                .find(|(id, _)| *id == settings.language)
+                .map_or_else(|| settings.language.clone(), |(_, name)| name),
+            SettingItem::Permadeath => tr(if settings.permadeath {
+                "settings.on"
+            } else {
+                "settings.off"
+            }),
+        }
+    }
+
+    /// Switch to the next (or previous) value of this setting.
+    fn cycle(&self, settings: &mut Settings, forward: bool) {
+        match self {
+            SettingItem::Language => {
+                let languages = locale::languages();
+                if languages.is_empty() {
+                    return;
+                }
+                let len = languages.len();
+                let index = languages
+                    .iter()
+                    .position(|(id, _)| *id == settings.language)
+                    .map_or(0, |i| {
+                        if forward {
+                            (i + 1) % len
+                        } else {
+                            (i + len - 1) % len
+                        }
+                    });
+                settings.language = languages[index].0.clone();
+                locale::set_language(&settings.language);
+            }
+            SettingItem::Permadeath => settings.permadeath = !settings.permadeath,
+        }
+    }
+}
+
+pub struct SettingsMenu {
+    state: TableState,
+}
+
+impl Default for SettingsMenu {
+    fn default() -> Self {
+        let mut state = TableState::default();
+        state.select(Some(0));
+
+        Self { state }
+    }
+}
+
+impl SettingsMenu {
+    pub fn view<B: Backend>(&mut self, f: &mut Frame<B>, settings: &Settings, area: Rect) {
+        let rows: Vec<Row> = ITEMS
+            .iter()
+            .map(|item| Row::new(vec![item.label(), format!("< {} >", item.value(settings))]))
+            .collect();
+
+        let table = Table::new(rows)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(tr("settings.title")),
+            )
+            .widths(&[Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("> ");
+
+        f.render_stateful_widget(table, area, &mut self.state);
+    }
+
+    pub fn on_key(&mut self, code: char, settings: &mut Settings) {
+        let selected = self.state.selected().unwrap_or(0);
+        match code {
+            'j' => self.state.select(Some((selected + 1) % ITEMS.len())),
+            'k' => self
+                .state
+                .select(Some((selected + ITEMS.len() - 1) % ITEMS.len())),
+            'h' | 'l' => {
+                ITEMS[selected].cycle(settings, code == 'l');
+                settings.save();
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,382 @@
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Spans,
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState, Tabs},
+    Frame,
+};
+
+use crate::game::{
+    locale::{tr, trf},
+    Alignment, AttributeType, Character, EquipSlot, GameState, Standing,
+};
+
+use super::player::Player;
+
+/// Sections of the character sheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SheetTab {
+    Attributes,
+    Status,
+    MartialArts,
+    Equipment,
+    Reputation,
+}
+
+impl SheetTab {
+    const ALL: [SheetTab; 5] = [
+        SheetTab::Attributes,
+        SheetTab::Status,
+        SheetTab::MartialArts,
+        SheetTab::Equipment,
+        SheetTab::Reputation,
+    ];
+
+    fn title(&self) -> String {
+        tr(match self {
+            SheetTab::Attributes => "sheet.tab.attributes",
+            SheetTab::Status => "sheet.tab.status",
+            SheetTab::MartialArts => "sheet.tab.martial_arts",
+            SheetTab::Equipment => "sheet.tab.equipment",
+            SheetTab::Reputation => "sheet.tab.reputation",
+        })
+    }
+}
+
+/// Full-screen character sheet, opened from the map.
+#[derive(Default)]
+pub struct CharacterSheet {
+    tab: usize,
+    /// Selected row in the attributes tab.
+    attr: usize,
+    /// Selected row in the martial arts tab.
+    skill: usize,
+    /// Selected row in the equipment tab.
+    slot: usize,
+}
+
+impl CharacterSheet {
+    pub fn on_key(&mut self, code: char, player: &Player) {
+        let len = SheetTab::ALL.len();
+        match code {
+            'h' => self.tab = (self.tab + len - 1) % len,
+            'l' => self.tab = (self.tab + 1) % len,
+            'j' | 'k' => {
+                let (row, len) = match SheetTab::ALL[self.tab] {
+                    SheetTab::Attributes => (&mut self.attr, AttributeType::ALL.len()),
+                    SheetTab::Equipment => (&mut self.slot, EquipSlot::ALL.len()),
+                    _ => (&mut self.skill, player.skills().len()),
+                };
+                *row = if code == 'j' {
+                    (*row + 1).min(len.saturating_sub(1))
+                } else {
+                    row.saturating_sub(1)
+                };
+            }
+            '1'..='9' => {
+                let index = code as usize - '1' as usize;
+                if index < len {
+                    self.tab = index;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The selected attribute if the attributes tab is shown.
+    pub fn selected_attr(&self) -> Option<AttributeType> {
+        match SheetTab::ALL[self.tab] {
+            SheetTab::Attributes => Some(AttributeType::ALL[self.attr]),
+            _ => None,
+        }
+    }
+
+    /// Index of the selected skill if the martial arts tab is shown.
+    pub fn selected_skill(&self, player: &Player) -> Option<usize> {
+        match SheetTab::ALL[self.tab] {
+            SheetTab::MartialArts if !player.skills().is_empty() => {
+                Some(self.skill.min(player.skills().len() - 1))
+            }
+            _ => None,
+        }
+    }
+
+    /// The selected slot if the equipment tab is shown.
+    pub fn selected_slot(&self) -> Option<EquipSlot> {
+        match SheetTab::ALL[self.tab] {
+            SheetTab::Equipment => Some(EquipSlot::ALL[self.slot]),
+            _ => None,
+        }
+    }
+
+    pub fn view<B: Backend>(&self, f: &mut Frame<B>, state: &GameState, area: Rect) {
+        let player = &state.player;
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(tr("sheet.title"));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .constraints(
+                [
+                    Constraint::Length(2),
+                    Constraint::Min(3),
+                    Constraint::Length(1),
+                    Constraint::Length(1),
+                ]
+                .as_ref(),
+            )
+            .horizontal_margin(1)
+            .split(inner);
+
+        let titles = SheetTab::ALL
+            .iter()
+            .map(|t| Spans::from(t.title()))
+            .collect();
+        let tabs = Tabs::new(titles).select(self.tab).highlight_style(
+            Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+        );
+        f.render_widget(tabs, chunks[0]);
+
+        match SheetTab::ALL[self.tab] {
+            // only show a selection when there are points to spend
+            SheetTab::Attributes if player.points() > 0 => {
+                player.draw_details(f, chunks[1], Some(self.attr))
+            }
+            SheetTab::Attributes => player.draw_long_desc(f, chunks[1]),
+            SheetTab::Status => self.view_status(f, state, chunks[1]),
+            SheetTab::MartialArts => self.view_skills(f, state, chunks[1]),
+            SheetTab::Equipment => self.view_equipment(f, state, chunks[1]),
+            SheetTab::Reputation => self.view_reputation(f, state, chunks[1]),
+        }
+
+        // show the result of the last action taken here
+        if let Some(msg) = state.messages.current() {
+            f.render_widget(Paragraph::new(msg.to_spans(state)), chunks[2]);
+        }
+
+        let hint = Paragraph::new(tr("sheet.hint")).style(Style::default().fg(Color::DarkGray));
+        f.render_widget(hint, chunks[3]);
+    }
+
+    fn view_reputation<B: Backend>(&self, f: &mut Frame<B>, state: &GameState, area: Rect) {
+        let reputation = state.player.reputation();
+        let chunks = Layout::default()
+            .constraints([Constraint::Length(3), Constraint::Min(1)].as_ref())
+            .split(area);
+
+        let sect = reputation
+            .sect()
+            .and_then(|id| state.faction_library.get(id))
+            .map_or_else(|| tr("faction.none"), |f| f.name.to_string());
+        let alignment = reputation.alignment();
+        let lines = vec![
+            Spans::from(trf("sheet.reputation.sect", &[&sect])),
+            Spans::from(trf(
+                "sheet.reputation.alignment",
+                &[
+                    &Alignment::of(alignment).to_string(),
+                    &alignment.to_string(),
+                ],
+            )),
+        ];
+        f.render_widget(Paragraph::new(lines), chunks[0]);
+
+        let rows: Vec<Row> = state
+            .faction_library
+            .iter()
+            .map(|faction| {
+                let value = reputation.get(&faction.id);
+                Row::new(vec![
+                    faction.name.to_string(),
+                    value.to_string(),
+                    Standing::of(value).to_string(),
+                    faction.description.to_string(),
+                ])
+            })
+            .collect();
+        let table = Table::new(rows)
+            .header(
+                Row::new(vec![
+                    tr("sheet.reputation.faction"),
+                    tr("sheet.reputation.value"),
+                    tr("sheet.reputation.standing"),
+                    tr("sheet.reputation.description"),
+                ])
+                .style(Style::default().fg(Color::DarkGray)),
+            )
+            .widths(&[
+                Constraint::Length(12),
+                Constraint::Length(8),
+                Constraint::Length(8),
+                Constraint::Length(60),
+            ]);
+        f.render_widget(table, chunks[1]);
+    }
+
+    fn view_skills<B: Backend>(&self, f: &mut Frame<B>, state: &GameState, area: Rect) {
+        let player = &state.player;
+        if player.skills().is_empty() {
+            f.render_widget(Paragraph::new(tr("sheet.none")), area);
+            return;
+        }
+
+        let rows: Vec<Row> = player
+            .skills()
+            .with(&state.rules.skills)
+            .map(|(s, skill)| {
+                let progress = if s.is_max(skill) {
+                    tr("skill.max")
+                } else {
+                    format!("{}/{}", s.progress, s.needed(skill))
+                };
+                Row::new(vec![
+                    skill.name.to_string(),
+                    skill.kind.to_string(),
+                    trf(
+                        "skill.level",
+                        &[&s.level.to_string(), &skill.max_level.to_string()],
+                    ),
+                    progress,
+                    skill.description.to_string(),
+                ])
+            })
+            .collect();
+        let table = Table::new(rows)
+            .header(
+                Row::new(vec![
+                    tr("sheet.skill.name"),
+                    tr("sheet.skill.kind"),
+                    tr("sheet.skill.level"),
+                    tr("sheet.skill.practice"),
+                    tr("sheet.skill.description"),
+                ])
+                .style(Style::default().fg(Color::DarkGray)),
+            )
+            .widths(&[
+                Constraint::Length(12),
+                Constraint::Length(8),
+                Constraint::Length(10),
+                Constraint::Length(10),
+                Constraint::Length(60),
+            ])
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("> ");
+
+        let mut state = TableState::default();
+        state.select(self.selected_skill(player));
+        f.render_stateful_widget(table, area, &mut state);
+    }
+
+    fn view_equipment<B: Backend>(&self, f: &mut Frame<B>, state: &GameState, area: Rect) {
+        let equipment = state.player.equipment();
+        let rows: Vec<Row> = EquipSlot::ALL
+            .iter()
+            .map(|slot| {
+                let id = match equipment.get(*slot) {
+                    Some(id) => id,
+                    None => return Row::new(vec![slot.to_string(), tr("sheet.none")]),
+                };
+                match state.rules.items.get(id) {
+                    Some(item) => {
+                        let effects: Vec<String> =
+                            item.modifiers.iter().map(|m| m.to_string()).collect();
+                        Row::new(vec![
+                            slot.to_string(),
+                            item.name.to_string(),
+                            item.weapon.map(|w| w.to_string()).unwrap_or_default(),
+                            effects.join(", "),
+                        ])
+                    }
+                    None => Row::new(vec![slot.to_string(), id.to_string()]),
+                }
+            })
+            .collect();
+        let table = Table::new(rows)
+            .header(
+                Row::new(vec![
+                    tr("sheet.equip.slot"),
+                    tr("sheet.equip.item"),
+                    tr("sheet.equip.kind"),
+                    tr("sheet.equip.effects"),
+                ])
+                .style(Style::default().fg(Color::DarkGray)),
+            )
+            .widths(&[
+                Constraint::Length(8),
+                Constraint::Length(12),
+                Constraint::Length(8),
+                Constraint::Length(60),
+            ])
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("> ");
+
+        let mut state = TableState::default();
+        state.select(Some(self.slot));
+        f.render_stateful_widget(table, area, &mut state);
+    }
+
+    fn view_status<B: Backend>(&self, f: &mut Frame<B>, state: &GameState, area: Rect) {
+        let player = &state.player;
+        if player.buffs().is_empty() && player.injuries().is_empty() {
+            f.render_widget(Paragraph::new(tr("sheet.none")), area);
+            return;
+        }
+
+        let injuries = player.injuries().iter().filter_map(|i| {
+            let rule = state.rules.injuries.get(i.kind)?;
+            let effects: Vec<String> = i.effects(rule).iter().map(|m| m.to_string()).collect();
+            let row = Row::new(vec![
+                i.kind.to_string(),
+                trf(
+                    "sheet.injury.severity",
+                    &[&i.severity.to_string(), &rule.max_severity.to_string()],
+                ),
+                String::new(),
+                effects.join(", "),
+            ]);
+            Some(row.style(Style::default().fg(Color::Red)))
+        });
+        let rows: Vec<Row> = injuries
+            .chain(player.buffs().iter().filter_map(|b| {
+                let buff = state.rules.buffs.get(&b.id)?;
+                let effects: Vec<String> = buff.modifiers.iter().map(|m| m.to_string()).collect();
+                Some(Row::new(vec![
+                    b.name(&state.rules.buffs),
+                    b.remaining.describe(),
+                    b.source.clone(),
+                    effects.join(", "),
+                ]))
+            }))
+            .collect();
+        let table = Table::new(rows)
+            .header(
+                Row::new(vec![
+                    tr("sheet.buff.name"),
+                    tr("sheet.buff.remaining"),
+                    tr("sheet.buff.source"),
+                    tr("sheet.buff.effects"),
+                ])
+                .style(Style::default().fg(Color::DarkGray)),
+            )
+            .widths(&[
+                Constraint::Length(12),
+                Constraint::Length(12),
+                Constraint::Length(12),
+                Constraint::Length(60),
+            ]);
+        f.render_widget(table, area);
+    }
+}
@@ -0,0 +1,221 @@
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Spans,
+    widgets::{Block, Borders, Paragraph, Row, Table, Tabs},
+    Frame,
+};
+
+use crate::game::{
+    locale::{tr, trf},
+    AttributeType, GameState, Merchant, Money, CURRENCY,
+};
+
+use super::{StatefulTable, TableData};
+
+/// Rows of the open tab, rebuilt from the game state before use.
+#[derive(Default)]
+struct Goods {
+    /// The player sells from the inventory instead of buying.
+    selling: bool,
+    rows: Vec<Vec<String>>,
+}
+
+impl TableData for Goods {
+    fn header(&self) -> Row<'_> {
+        let price = if self.selling {
+            "shop.offer"
+        } else {
+            "shop.price"
+        };
+        Row::new(vec![tr("shop.name"), tr("shop.count"), tr(price)])
+    }
+
+    fn data(&self) -> Vec<Row<'_>> {
+        self.rows.iter().map(|r| Row::new(r.clone())).collect()
+    }
+
+    fn len(&self) -> usize {
+        self.rows.len()
+    }
+}
+
+/// Full-screen shop, opened by walking into a merchant.
+pub struct ShopMenu {
+    list: StatefulTable<Goods>,
+}
+
+impl Default for ShopMenu {
+    fn default() -> Self {
+        Self {
+            list: StatefulTable::new(Goods::default()),
+        }
+    }
+}
+
+impl ShopMenu {
+    pub fn on_key(&mut self, code: char, state: &mut GameState) {
+        self.refresh(state);
+        let empty = self.list.items.len() == 0;
+        match code {
+            'h' | 'l' => {
+                self.list.items.selling = !self.list.items.selling;
+                self.list.unselect();
+            }
+            'j' if !empty => self.list.next(),
+            'k' if !empty => self.list.previous(),
+            't' => {
+                if let Some(index) = self.list.state.selected() {
+                    if self.list.items.selling {
+                        state.sell(index);
+                    } else {
+                        state.buy(index);
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.refresh(state);
+    }
+
+    /// Rebuild the rows of the open tab and keep the selection on them.
+    fn refresh(&mut self, state: &GameState) {
+        let merchant = match merchant(state) {
+            Some(merchant) => merchant,
+            None => return,
+        };
+        let reputation = state.player.reputation();
+        let chr = state.player.effective_attr().get(AttributeType::Chr);
+
+        self.list.items.rows = if self.list.items.selling {
+            state
+                .player
+                .inventory()
+                .iter()
+                .map(|s| match state.rules.items.get(&s.item) {
+                    Some(item) => {
+                        let offer = if merchant.buys(item) {
+                            Money(merchant.buying_price(item, reputation, chr)).to_string()
+                        } else {
+                            tr("shop.unwanted_short")
+                        };
+                        vec![item.name.to_string(), s.count.to_string(), offer]
+                    }
+                    None => vec![s.item.clone(), s.count.to_string()],
+                })
+                .collect()
+        } else {
+            let stock = state.shops.get(&merchant.id).map_or(&[][..], |s| &s.stock);
+            stock
+                .iter()
+                .map(|e| match state.rules.items.get(&e.item) {
+                    Some(item) => vec![
+                        item.name.to_string(),
+                        e.count.to_string(),
+                        Money(merchant.selling_price(item, reputation, chr)).to_string(),
+                    ],
+                    None => vec![e.item.clone(), e.count.to_string()],
+                })
+                .collect()
+        };
+
+        let len = self.list.items.len();
+        match self.list.state.selected() {
+            Some(i) if i >= len => self.list.state.select(len.checked_sub(1)),
+            None if len > 0 => self.list.state.select(Some(0)),
+            _ => {}
+        }
+    }
+
+    pub fn view<B: Backend>(&mut self, f: &mut Frame<B>, state: &GameState, area: Rect) {
+        let merchant = match merchant(state) {
+            Some(merchant) => merchant,
+            None => return,
+        };
+        self.refresh(state);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(merchant.name.to_string());
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .constraints(
+                [
+                    Constraint::Length(1),
+                    Constraint::Length(2),
+                    Constraint::Min(3),
+                    Constraint::Length(1),
+                    Constraint::Length(1),
+                ]
+                .as_ref(),
+            )
+            .horizontal_margin(1)
+            .split(inner);
+
+        // draw the money carried and how the merchant feels about it
+        {
+            let money = Money(state.player.inventory().count(CURRENCY));
+            let standing = merchant.standing(state.player.reputation());
+            let purse = trf("shop.purse", &[&money.to_string(), &standing.to_string()]);
+            f.render_widget(Paragraph::new(purse), chunks[0]);
+        }
+
+        let titles = vec![Spans::from(tr("shop.buy")), Spans::from(tr("shop.sell"))];
+        let tabs = Tabs::new(titles)
+            .select(self.list.items.selling as usize)
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            );
+        f.render_widget(tabs, chunks[1]);
+
+        if self.list.items.len() == 0 {
+            let empty = if self.list.items.selling {
+                "inventory.empty"
+            } else {
+                "shop.sold_out"
+            };
+            f.render_widget(Paragraph::new(tr(empty)), chunks[2]);
+        } else {
+            let table = Table::new(self.list.items.data())
+                .header(
+                    self.list
+                        .items
+                        .header()
+                        .style(Style::default().fg(Color::DarkGray)),
+                )
+                .widths(&[
+                    Constraint::Length(14),
+                    Constraint::Length(8),
+                    Constraint::Length(14),
+                ])
+                .highlight_style(
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .highlight_symbol("> ");
+            f.render_stateful_widget(table, chunks[2], &mut self.list.state);
+        }
+
+        // show the result of the last trade
+        if let Some(msg) = state.messages.current() {
+            f.render_widget(Paragraph::new(msg.to_spans(state)), chunks[3]);
+        }
+
+        let hint = Paragraph::new(tr("shop.hint")).style(Style::default().fg(Color::DarkGray));
+        f.render_widget(hint, chunks[4]);
+    }
+}
+
+/// The merchant the player trades with.
+fn merchant(state: &GameState) -> Option<&Merchant> {
+    state
+        .shop
+        .as_ref()
+        .and_then(|id| state.merchant_library.get(id))
+}
@@ -0,0 +1,209 @@
+use tui::{
+    style::Style,
+    text::{Span, Spans},
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Punctuation that must not start a line.
+const NO_LINE_START: &str = "，。、；：？！）》」』】〕”’…—·,.;:?!)]}%";
+/// Punctuation that must not end a line.
+const NO_LINE_END: &str = "（《「『【〔“‘([{";
+
+/// Number of terminal columns used to display a string.
+///
+/// Wide characters such as Chinese take two columns.
+pub fn width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+/// Pad a string with spaces on the right to fill `columns` columns.
+pub fn pad(text: &str, columns: usize) -> String {
+    let fill = columns.saturating_sub(width(text));
+    format!("{}{}", text, " ".repeat(fill))
+}
+
+/// Characters that can be broken between on their own.
+fn is_wide(c: char) -> bool {
+    c.width().unwrap_or(0) > 1
+}
+
+/// A piece of text that is kept on one line when wrapping.
+struct Unit {
+    chars: Vec<(char, Style)>,
+    width: usize,
+    space: bool,
+}
+
+impl Unit {
+    fn new(space: bool) -> Unit {
+        Unit {
+            chars: vec![],
+            width: 0,
+            space,
+        }
+    }
+
+    fn push(&mut self, c: char, style: Style) {
+        self.width += c.width().unwrap_or(0);
+        self.chars.push((c, style));
+    }
+
+    fn last(&self) -> Option<char> {
+        self.chars.last().map(|(c, _)| *c)
+    }
+}
+
+/// Split styled text into units that lines may break between.
+///
+/// Each wide character is its own unit, other words are kept together.
+/// Closing punctuation sticks to the text before it and opening
+/// punctuation sticks to the text after it.
+fn units(spans: &[Span]) -> Vec<Unit> {
+    let mut output: Vec<Unit> = vec![];
+    let mut glue_next = false;
+
+    for span in spans {
+        for c in span.content.chars() {
+            let space = c.is_whitespace();
+            let join = match output.last() {
+                None => false,
+                Some(_) if glue_next => !space,
+                Some(last) if NO_LINE_START.contains(c) => !last.space,
+                Some(last) => {
+                    !space && !last.space && !is_wide(c) && last.last().is_some_and(|l| !is_wide(l))
+                }
+            };
+
+            if join {
+                output.last_mut().unwrap().push(c, span.style);
+            } else {
+                let mut unit = Unit::new(space);
+                unit.push(c, span.style);
+                output.push(unit);
+            }
+
+            glue_next = NO_LINE_END.contains(c);
+        }
+    }
+
+    output
+}
+
+/// Merge characters with the same style into spans.
+fn to_spans(chars: Vec<(char, Style)>) -> Spans<'static> {
+    let mut spans: Vec<Span> = vec![];
+    let mut text = String::new();
+    let mut current: Option<Style> = None;
+
+    for (c, style) in chars {
+        if let Some(last) = current.filter(|s| *s != style) {
+            spans.push(Span::styled(std::mem::take(&mut text), last));
+        }
+        current = Some(style);
+        text.push(c);
+    }
+    if let Some(style) = current {
+        spans.push(Span::styled(text, style));
+    }
+
+    Spans::from(spans)
+}
+
+/// Wrap styled text into lines at most `columns` wide.
+///
+/// Unlike [`tui::widgets::Wrap`], Chinese text can break between any two
+/// characters, while punctuation stays attached to its sentence. Spaces
+/// at the start of a wrapped line are dropped.
+pub fn wrap(spans: &Spans, columns: usize) -> Vec<Spans<'static>> {
+    let columns = columns.max(2);
+    let mut lines = vec![];
+    let mut line: Vec<(char, Style)> = vec![];
+    let mut line_width = 0;
+
+    for unit in units(&spans.0) {
+        if unit.space && line.is_empty() && !lines.is_empty() {
+            continue;
+        }
+
+        if line_width + unit.width > columns && !line.is_empty() {
+            lines.push(to_spans(std::mem::take(&mut line)));
+            line_width = 0;
+            if unit.space {
+                continue;
+            }
+        }
+
+        if unit.width > columns {
+            // break words longer than a line by characters
+            for (c, style) in unit.chars {
+                let w = c.width().unwrap_or(0);
+                if line_width + w > columns && !line.is_empty() {
+                    lines.push(to_spans(std::mem::take(&mut line)));
+                    line_width = 0;
+                }
+                line.push((c, style));
+                line_width += w;
+            }
+        } else {
+            line_width += unit.width;
+            line.extend(unit.chars);
+        }
+    }
+
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(to_spans(line));
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use tui::style::Color;
+
+    use super::*;
+
+    fn lines(text: &str, columns: usize) -> Vec<String> {
+        wrap(&Spans::from(text.to_string()), columns)
+            .iter()
+            .map(|l| l.0.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn widths() {
+        assert_eq!(width("abc"), 3);
+        assert_eq!(width("浣花村"), 6);
+        assert_eq!(width("村a"), 3);
+        assert_eq!(pad("村", 4), "村  ");
+        assert_eq!(pad("浣花村", 4), "浣花村");
+    }
+
+    #[test]
+    fn wrap_words() {
+        assert_eq!(lines("hello world", 5), vec!["hello", "world"]);
+        assert_eq!(lines("abcdefgh", 3), vec!["abc", "def", "gh"]);
+        assert_eq!(lines("", 5), vec![""]);
+    }
+
+    #[test]
+    fn wrap_chinese() {
+        assert_eq!(lines("浣花村的河水", 6), vec!["浣花村", "的河水"]);
+        // closing punctuation stays with the character before it
+        assert_eq!(lines("一二三。四", 6), vec!["一二", "三。四"]);
+        // opening punctuation stays with the character after it
+        assert_eq!(lines("一二（三", 6), vec!["一二", "（三"]);
+    }
+
+    #[test]
+    fn wrap_keeps_styles() {
+        let red = Style::default().fg(Color::Red);
+        let spans = Spans::from(vec![Span::raw("找到"), Span::styled("野猪", red)]);
+        let wrapped = wrap(&spans, 20);
+
+        assert_eq!(wrapped.len(), 1);
+        assert_eq!(wrapped[0].0.len(), 2);
+        assert_eq!(wrapped[0].0[1].content, "野猪");
+        assert_eq!(wrapped[0].0[1].style, red);
+    }
+}
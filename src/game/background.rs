@@ -0,0 +1,52 @@
+use assets_manager::{loader, Asset, AssetCache};
+use serde::Deserialize;
+
+use super::{locale::Text, EventCommand};
+
+/// Origin of a new character, chosen during character creation.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Background {
+    pub id: String,
+    pub name: Text,
+    pub description: Text,
+    /// Commands run when a game starts with this background.
+    #[serde(default)]
+    pub commands: Vec<EventCommand>,
+}
+
+/// All backgrounds, loaded from `assets/backgrounds.json`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Backgrounds {
+    backgrounds: Vec<Background>,
+}
+
+impl Asset for Backgrounds {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+impl Backgrounds {
+    pub fn load(cache: &AssetCache) -> Backgrounds {
+        let background_file = "backgrounds";
+        let handle = cache.load_expect::<Backgrounds>(background_file);
+
+        handle.read().to_owned()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Background> {
+        self.backgrounds.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Background> {
+        self.backgrounds.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.backgrounds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.backgrounds.is_empty()
+    }
+}
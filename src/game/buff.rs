@@ -1,4 +1,367 @@
+use std::fmt;
+
+use assets_manager::{loader, Asset, AssetCache};
 use serde::{Deserialize, Serialize};
 
+use super::{
+    locale::{tr, trf, Text},
+    AttributeType, PropertyType,
+};
+
+/// A temporary change to attributes or properties, defined in
+/// `assets/buffs.json`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum Buff {}
+pub struct Buff {
+    pub id: String,
+    pub name: Text,
+    pub duration: BuffDuration,
+    #[serde(default)]
+    pub stacking: Stacking,
+    pub modifiers: Vec<Modifier>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BuffDuration {
+    /// Lasts for minutes of game time.
+    Minutes(u32),
+    /// Lasts for a number of player turns.
+    Turns(u32),
+    Permanent,
+}
+
+impl BuffDuration {
+    pub fn is_over(&self) -> bool {
+        matches!(self, BuffDuration::Minutes(0) | BuffDuration::Turns(0))
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            BuffDuration::Minutes(m) => trf("buff.minutes", &[&m.to_string()]),
+            BuffDuration::Turns(t) => trf("buff.turns", &[&t.to_string()]),
+            BuffDuration::Permanent => tr("buff.permanent"),
+        }
+    }
+}
+
+/// What happens when a buff is applied while it is already active.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Stacking {
+    /// Reset the duration of the active buff.
+    #[default]
+    Refresh,
+    /// Add a stack up to the limit and reset the duration.
+    Stack(u32),
+    /// Keep the active buff and ignore the new one.
+    Ignore,
+    /// Add another copy with its own duration.
+    Independent,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Modifier {
+    pub target: ModTarget,
+    pub op: ModOp,
+}
+
+/// Value changed by a modifier. Only maxima of properties can be
+/// modified, current values follow them.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ModTarget {
+    Attr(AttributeType),
+    Prop(PropertyType),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ModOp {
+    Add(f64),
+    /// Multiply by the factor, e.g. 1.1 for +10%.
+    Mul(f64),
+}
+
+impl fmt::Display for ModTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModTarget::Attr(t) => write!(f, "{}", t),
+            ModTarget::Prop(t) => write!(f, "{}", t),
+        }
+    }
+}
+
+impl fmt::Display for Modifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.op {
+            ModOp::Add(x) => write!(f, "{} {:+}", self.target, x),
+            ModOp::Mul(x) => write!(f, "{} ×{}", self.target, x),
+        }
+    }
+}
+
+/// Apply modifiers to a base value. All additions are applied before
+/// multiplications.
+pub fn apply_modifiers<'a, I>(base: f64, target: ModTarget, modifiers: I) -> f64
+where
+    I: Iterator<Item = (&'a Modifier, u32)> + Clone,
+{
+    let matching = modifiers.filter(|(m, _)| m.target == target);
+    let added = matching.clone().fold(base, |v, (m, stacks)| match m.op {
+        ModOp::Add(x) => v + x * stacks as f64,
+        ModOp::Mul(_) => v,
+    });
+
+    matching.fold(added, |v, (m, stacks)| match m.op {
+        ModOp::Add(_) => v,
+        ModOp::Mul(x) => v * x.powi(stacks as i32),
+    })
+}
+
+/// A buff affecting a character.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveBuff {
+    /// Buff id.
+    pub id: String,
+    /// What applied the buff, e.g. an item or event.
+    pub source: String,
+    pub stacks: u32,
+    pub remaining: BuffDuration,
+}
+
+impl ActiveBuff {
+    /// Name of the buff with its stacks, or its id if it is not defined.
+    pub fn name(&self, library: &BuffLibrary) -> String {
+        let name = library
+            .get(&self.id)
+            .map_or(self.id.clone(), |b| b.name.to_string());
+        if self.stacks > 1 {
+            format!("{}×{}", name, self.stacks)
+        } else {
+            name
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Buffs(Vec<ActiveBuff>);
+
+impl Buffs {
+    pub fn apply(&mut self, buff: &Buff, source: &str) {
+        let active = self.0.iter_mut().find(|b| b.id == buff.id);
+
+        match (active, buff.stacking) {
+            (Some(_), Stacking::Ignore) => {}
+            (Some(active), Stacking::Refresh) => active.remaining = buff.duration,
+            (Some(active), Stacking::Stack(max)) => {
+                active.stacks = (active.stacks + 1).min(max.max(1));
+                active.remaining = buff.duration;
+            }
+            (None, _) | (Some(_), Stacking::Independent) => self.0.push(ActiveBuff {
+                id: buff.id.clone(),
+                source: source.to_string(),
+                stacks: 1,
+                remaining: buff.duration,
+            }),
+        }
+    }
+
+    /// Remove all copies of a buff. Return true if any was removed.
+    pub fn remove(&mut self, id: &str) -> bool {
+        let len = self.0.len();
+        self.0.retain(|b| b.id != id);
+        len != self.0.len()
+    }
+
+    /// Stacks of a buff, 0 if it is not active.
+    pub fn stacks(&self, id: &str) -> u32 {
+        self.0.iter().filter(|b| b.id == id).map(|b| b.stacks).sum()
+    }
+
+    /// Let game time pass. Return the buffs that wore off.
+    pub fn pass_minutes(&mut self, minutes: u32) -> Vec<ActiveBuff> {
+        for b in self.0.iter_mut() {
+            if let BuffDuration::Minutes(m) = b.remaining {
+                b.remaining = BuffDuration::Minutes(m.saturating_sub(minutes));
+            }
+        }
+        self.take_expired()
+    }
+
+    /// End a turn. Return the buffs that wore off.
+    pub fn pass_turn(&mut self) -> Vec<ActiveBuff> {
+        for b in self.0.iter_mut() {
+            if let BuffDuration::Turns(t) = b.remaining {
+                b.remaining = BuffDuration::Turns(t.saturating_sub(1));
+            }
+        }
+        self.take_expired()
+    }
+
+    fn take_expired(&mut self) -> Vec<ActiveBuff> {
+        let (expired, active) = std::mem::take(&mut self.0)
+            .into_iter()
+            .partition(|b| b.remaining.is_over());
+        self.0 = active;
+        expired
+    }
+
+    /// All modifiers of active buffs with their stacks.
+    pub fn modifiers<'a>(
+        &'a self,
+        library: &'a BuffLibrary,
+    ) -> impl Iterator<Item = (&'a Modifier, u32)> + Clone {
+        self.0
+            .iter()
+            .filter_map(|b| library.get(&b.id).map(|buff| (b, buff)))
+            .flat_map(|(b, buff)| buff.modifiers.iter().map(move |m| (m, b.stacks)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ActiveBuff> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// All buff definitions.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BuffLibrary {
+    buffs: Vec<Buff>,
+}
+
+impl Asset for BuffLibrary {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+impl BuffLibrary {
+    pub fn load(cache: &AssetCache) -> BuffLibrary {
+        let buff_file = "buffs";
+        let handle = cache.load_expect::<BuffLibrary>(buff_file);
+
+        handle.read().to_owned()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Buff> {
+        self.buffs.iter().find(|b| b.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Buff> {
+        self.buffs.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buff(stacking: Stacking, modifiers: Vec<Modifier>) -> Buff {
+        Buff {
+            id: "test".to_string(),
+            name: Text::default(),
+            duration: BuffDuration::Turns(3),
+            stacking,
+            modifiers,
+        }
+    }
+
+    fn str_mod(op: ModOp) -> Modifier {
+        Modifier {
+            target: ModTarget::Attr(AttributeType::Str),
+            op,
+        }
+    }
+
+    /// Apply a buff, let a turn pass, then apply it again.
+    fn apply_twice(stacking: Stacking) -> Buffs {
+        let buff = buff(stacking, vec![]);
+        let mut buffs = Buffs::default();
+        buffs.apply(&buff, "a");
+        buffs.pass_turn();
+        buffs.apply(&buff, "b");
+        buffs
+    }
+
+    fn remaining(buffs: &Buffs) -> Vec<BuffDuration> {
+        buffs.iter().map(|b| b.remaining).collect()
+    }
+
+    #[test]
+    fn refresh_resets_duration() {
+        let buffs = apply_twice(Stacking::Refresh);
+        assert_eq!(buffs.stacks("test"), 1);
+        assert_eq!(remaining(&buffs), vec![BuffDuration::Turns(3)]);
+    }
+
+    #[test]
+    fn stack_up_to_limit() {
+        let buffs = apply_twice(Stacking::Stack(2));
+        assert_eq!(buffs.stacks("test"), 2);
+        assert_eq!(remaining(&buffs), vec![BuffDuration::Turns(3)]);
+
+        let mut buffs = buffs;
+        buffs.apply(&buff(Stacking::Stack(2), vec![]), "c");
+        assert_eq!(buffs.stacks("test"), 2);
+    }
+
+    #[test]
+    fn ignore_keeps_active() {
+        let buffs = apply_twice(Stacking::Ignore);
+        assert_eq!(buffs.stacks("test"), 1);
+        assert_eq!(remaining(&buffs), vec![BuffDuration::Turns(2)]);
+        assert_eq!(buffs.iter().next().unwrap().source, "a");
+    }
+
+    #[test]
+    fn independent_copies() {
+        let buffs = apply_twice(Stacking::Independent);
+        assert_eq!(buffs.stacks("test"), 2);
+        assert_eq!(
+            remaining(&buffs),
+            vec![BuffDuration::Turns(2), BuffDuration::Turns(3)]
+        );
+    }
+
+    #[test]
+    fn expire_after_turns() {
+        let mut buffs = apply_twice(Stacking::Independent);
+        assert!(buffs.pass_turn().is_empty());
+        let expired = buffs.pass_turn();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].source, "a");
+        assert_eq!(buffs.stacks("test"), 1);
+    }
+
+    #[test]
+    fn add_before_multiply() {
+        let modifiers = [
+            str_mod(ModOp::Mul(2.0)),
+            str_mod(ModOp::Add(3.0)),
+            Modifier {
+                target: ModTarget::Attr(AttributeType::Dex),
+                op: ModOp::Add(100.0),
+            },
+        ];
+        let once = modifiers.iter().map(|m| (m, 1));
+        let target = ModTarget::Attr(AttributeType::Str);
+        assert_eq!(apply_modifiers(10.0, target, once), 26.0);
+
+        // stacks add and multiply again
+        let twice = modifiers.iter().map(|m| (m, 2));
+        assert_eq!(apply_modifiers(10.0, target, twice), 64.0);
+    }
+
+    #[test]
+    fn modifiers_of_defined_buffs() {
+        let library = BuffLibrary {
+            buffs: vec![buff(Stacking::Stack(3), vec![str_mod(ModOp::Add(1.0))])],
+        };
+        let mut buffs = apply_twice(Stacking::Stack(3));
+        let mut other = buff(Stacking::Refresh, vec![]);
+        other.id = "undefined".to_string();
+        buffs.apply(&other, "d");
+
+        let modifiers: Vec<(&Modifier, u32)> = buffs.modifiers(&library).collect();
+        assert_eq!(modifiers, vec![(&library.buffs[0].modifiers[0], 2)]);
+    }
+}
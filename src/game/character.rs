@@ -3,9 +3,11 @@ use std::{collections::HashMap, fmt};
 use serde::{Deserialize, Serialize};
 use tui::{backend::Backend, layout::Rect, Frame};
 
+use super::{locale::tr, Rules};
+
 pub trait Character {
     fn draw_long_desc<B: Backend>(&self, f: &mut Frame<B>, area: Rect);
-    fn draw_short_desc<B: Backend>(&self, f: &mut Frame<B>, area: Rect);
+    fn draw_short_desc<B: Backend>(&self, f: &mut Frame<B>, area: Rect, rules: &Rules);
 
     fn symbol(&self) -> &str;
 
@@ -52,7 +54,7 @@ impl Pos {
     }
 }
 
-#[derive(Default, Serialize, Deserialize, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Attribute {
     pub con: usize,
     pub str: usize,
@@ -62,18 +64,64 @@ pub struct Attribute {
 }
 
 impl Attribute {
-    pub fn new(con: usize, str: usize, dex: usize, int: usize, chr: usize) -> Self {
-        Self {
-            con,
-            str,
-            dex,
-            int,
-            chr,
+    pub fn get(&self, attr: AttributeType) -> usize {
+        match attr {
+            AttributeType::Con => self.con,
+            AttributeType::Str => self.str,
+            AttributeType::Dex => self.dex,
+            AttributeType::Int => self.int,
+            AttributeType::Chr => self.chr,
+        }
+    }
+
+    pub fn set(&mut self, attr: AttributeType, value: usize) {
+        match attr {
+            AttributeType::Con => self.con = value,
+            AttributeType::Str => self.str = value,
+            AttributeType::Dex => self.dex = value,
+            AttributeType::Int => self.int = value,
+            AttributeType::Chr => self.chr = value,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum AttributeType {
+    Con,
+    Str,
+    Dex,
+    Int,
+    Chr,
+}
+
+impl AttributeType {
+    pub const ALL: [AttributeType; 5] = [
+        AttributeType::Con,
+        AttributeType::Str,
+        AttributeType::Dex,
+        AttributeType::Int,
+        AttributeType::Chr,
+    ];
+
+    /// Name used in formulas and locale keys, e.g. `con`.
+    pub fn key(&self) -> &'static str {
+        match self {
+            AttributeType::Con => "con",
+            AttributeType::Str => "str",
+            AttributeType::Dex => "dex",
+            AttributeType::Int => "int",
+            AttributeType::Chr => "chr",
         }
     }
 
-    pub fn human() -> Self {
-        Self::new(10, 10, 10, 10, 10)
+    pub fn from_key(key: &str) -> Option<AttributeType> {
+        Self::ALL.into_iter().find(|t| t.key() == key)
+    }
+}
+
+impl fmt::Display for AttributeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", tr(&format!("attr.{}", self.key())))
     }
 }
 
@@ -83,38 +131,76 @@ pub struct Property {
 }
 
 impl Property {
-    pub fn from_attrs(attr: Attribute) -> Self {
-        let (max_jing, max_qi, max_shen) = (
-            PropertyValue::calc_jing(&attr),
-            PropertyValue::calc_qi(&attr),
-            PropertyValue::calc_shen(&attr),
-        );
-
-        Self {
-            props: HashMap::from([
-                (PropertyType::Jing, max_jing),
-                (PropertyType::Qi, max_qi),
-                (PropertyType::Shen, max_shen),
-                (PropertyType::MaxJing, max_jing),
-                (PropertyType::MaxQi, max_qi),
-                (PropertyType::MaxShen, max_shen),
-            ]),
-        }
-    }
-
     pub fn get(&self, prop: &PropertyType) -> Option<&PropertyValue> {
         self.props.get(prop)
     }
+
+    /// Get a value as number. Missing values count as 0.
+    pub fn number(&self, prop: &PropertyType) -> f64 {
+        self.get(prop).map_or(0.0, |v| v.unwrap_number())
+    }
+
+    pub fn set(&mut self, prop: PropertyType, value: PropertyValue) {
+        self.props.insert(prop, value);
+    }
+
+    /// Add to a current value, keeping it between 0 and its maximum.
+    pub fn change(&mut self, prop: PropertyType, delta: f64) {
+        let value = self.number(&prop) + delta;
+        let value = match prop.max() {
+            Some(max) => value.min(self.number(&max)),
+            None => value,
+        };
+        self.set(prop, PropertyValue::Number(value.max(0.0)));
+    }
+
+    /// Replace the maximum of a current value and keep the value within
+    /// it. A missing current value starts at the maximum.
+    pub fn set_max(&mut self, cur: PropertyType, value: f64) {
+        let max = match cur.max() {
+            Some(max) => max,
+            None => return,
+        };
+        let current = self
+            .get(&cur)
+            .map_or(value, |v| v.unwrap_number().min(value));
+        self.set(max, PropertyValue::Number(value));
+        self.set(cur, PropertyValue::Number(current));
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq)]
 pub enum PropertyType {
     Jing,
     Qi,
     Shen,
+    /// Inner energy (内力).
+    Neili,
     MaxJing,
     MaxQi,
     MaxShen,
+    MaxNeili,
+}
+
+impl PropertyType {
+    /// Properties with a current value and a maximum.
+    pub const CURRENT: [PropertyType; 4] = [
+        PropertyType::Jing,
+        PropertyType::Qi,
+        PropertyType::Shen,
+        PropertyType::Neili,
+    ];
+
+    /// The maximum of a current value, e.g. MaxJing for Jing.
+    pub fn max(&self) -> Option<PropertyType> {
+        match self {
+            PropertyType::Jing => Some(PropertyType::MaxJing),
+            PropertyType::Qi => Some(PropertyType::MaxQi),
+            PropertyType::Shen => Some(PropertyType::MaxShen),
+            PropertyType::Neili => Some(PropertyType::MaxNeili),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for PropertyType {
@@ -122,14 +208,16 @@ impl fmt::Display for PropertyType {
         write!(
             f,
             "{}",
-            match self {
-                PropertyType::Jing => "精",
-                PropertyType::Qi => "气",
-                PropertyType::Shen => "神",
-                PropertyType::MaxJing => "精上限",
-                PropertyType::MaxQi => "气上限",
-                PropertyType::MaxShen => "神上限",
-            }
+            tr(match self {
+                PropertyType::Jing => "prop.jing",
+                PropertyType::Qi => "prop.qi",
+                PropertyType::Shen => "prop.shen",
+                PropertyType::Neili => "prop.neili",
+                PropertyType::MaxJing => "prop.max_jing",
+                PropertyType::MaxQi => "prop.max_qi",
+                PropertyType::MaxShen => "prop.max_shen",
+                PropertyType::MaxNeili => "prop.max_neili",
+            })
         )
     }
 }
@@ -152,24 +240,6 @@ impl fmt::Display for PropertyValue {
 }
 
 impl PropertyValue {
-    pub fn calc_jing(attr: &Attribute) -> PropertyValue {
-        let formula = |con: usize| -> f64 { con as f64 * 5.0 };
-
-        PropertyValue::Number(formula(attr.con))
-    }
-
-    pub fn calc_qi(attr: &Attribute) -> PropertyValue {
-        let formula = |con: usize| -> f64 { con as f64 * 5.0 };
-
-        PropertyValue::Number(formula(attr.con))
-    }
-
-    pub fn calc_shen(attr: &Attribute) -> PropertyValue {
-        let formula = |con: usize| -> f64 { con as f64 * 5.0 };
-
-        PropertyValue::Number(formula(attr.int))
-    }
-
     pub fn unwrap_number(&self) -> f64 {
         match self {
             Self::Number(v) => *v,
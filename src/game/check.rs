@@ -0,0 +1,904 @@
+use std::{collections::HashSet, error::Error};
+
+use assets_manager::{AssetCache, Compound};
+
+use crate::components::{
+    map::{Maps, Passage, Requirement, Tiles, World},
+    message::{MsgStyle, Theme},
+    text,
+};
+
+use super::{
+    locale::{self, Text, FALLBACK_LANGUAGE},
+    template::Placeholder,
+    Activity, AttributeType, Backgrounds, BuffLibrary, Condition, Defeat, DefeatOutcome,
+    EnemyLibrary, EquipSlot, EventCommand, EventSystem, FactionLibrary, Formulas, GameSwitch,
+    GameVariable, InjuryLibrary, ItemCategory, ItemLibrary, Levels, MerchantLibrary, ModTarget,
+    Modifier, PropertyType, RecipeLibrary, Regen, ResourceLibrary, SkillLibrary, CURRENCY,
+};
+
+/// Assets that other assets refer to.
+#[derive(Default)]
+struct Library {
+    buffs: BuffLibrary,
+    enemies: EnemyLibrary,
+    factions: FactionLibrary,
+    formulas: Formulas,
+    injuries: InjuryLibrary,
+    items: ItemLibrary,
+    merchants: MerchantLibrary,
+    skills: SkillLibrary,
+    switches: GameSwitch,
+    theme: Theme,
+    tiles: Tiles,
+    variables: GameVariable,
+}
+
+/// Check game assets for mistakes that only show up while playing.
+///
+/// Return a list of human readable problems. An empty list means all
+/// checked assets are fine.
+pub fn check_assets(cache: &AssetCache) -> Vec<String> {
+    let mut problems = vec![];
+
+    let library = Library {
+        buffs: load(cache, "buffs", &mut problems),
+        enemies: load(cache, "enemies", &mut problems),
+        factions: load(cache, "factions", &mut problems),
+        formulas: load(cache, "formulas", &mut problems),
+        injuries: load(cache, "injuries", &mut problems),
+        items: load(cache, "items", &mut problems),
+        merchants: load(cache, "merchants", &mut problems),
+        skills: load(cache, "skills", &mut problems),
+        switches: load(cache, "switches", &mut problems),
+        theme: load(cache, "theme", &mut problems),
+        tiles: load(cache, "tiles", &mut problems),
+        variables: load(cache, "variables", &mut problems),
+    };
+
+    check_locales(cache, &mut problems);
+    check_factions(&library, &mut problems);
+    check_buffs(&library, &mut problems);
+    check_skills(&library, &mut problems);
+    check_items(&library, &mut problems);
+    check_injuries(&library, &mut problems);
+    check_enemies(&library, &mut problems);
+    check_merchants(&library, &mut problems);
+    check_tiles(&library, &mut problems);
+
+    let recipes: RecipeLibrary = load(cache, "recipes", &mut problems);
+    check_recipes(&recipes, &library, &mut problems);
+
+    let resources: ResourceLibrary = load(cache, "resources", &mut problems);
+    check_resources(&resources, &library, &mut problems);
+
+    check_formulas(&library, &mut problems);
+
+    let regen: Regen = load(cache, "regen", &mut problems);
+    check_regen(&regen, &library, &mut problems);
+
+    let levels: Levels = load(cache, "levels", &mut problems);
+    if levels.experience().windows(2).any(|w| w[0] >= w[1]) {
+        problems.push("levels: experience must grow with each level".to_string());
+    }
+
+    let defeat: Defeat = load(cache, "defeat", &mut problems);
+    check_defeat(&defeat, &library, &mut problems);
+
+    let map = Maps::HuanHuaCun("tiles".to_string());
+    let world: World = load(cache, map.map_file(), &mut problems);
+    check_map(map.map_file(), &world, &library, &mut problems);
+
+    let backgrounds: Backgrounds = load(cache, "backgrounds", &mut problems);
+    check_backgrounds(&backgrounds, &library, &mut problems);
+
+    match cache.load::<EventSystem>("events") {
+        Ok(handle) => check_events(&handle.read(), &library, &mut problems),
+        Err(e) => problems.push(format!("events: {}", describe(&e))),
+    }
+
+    problems
+}
+
+/// Load an asset, or record the problem and use the default value.
+fn load<A: Compound + Clone + Default>(
+    cache: &AssetCache,
+    id: &str,
+    problems: &mut Vec<String>,
+) -> A {
+    match cache.load::<A>(id) {
+        Ok(handle) => handle.read().clone(),
+        Err(e) => {
+            problems.push(format!("{}: {}", id, describe(&e)));
+            A::default()
+        }
+    }
+}
+
+fn check_events(events: &EventSystem, library: &Library, problems: &mut Vec<String>) {
+    for event in events.events() {
+        let mut report = |problem: String| {
+            problems.push(format!("events: event {}: {}", event.id(), problem));
+        };
+
+        let contents = event
+            .messages()
+            .iter()
+            .flat_map(|m| m.all_contents())
+            .flat_map(|(_, c)| c);
+        for (text, style) in contents {
+            for key in super::template::keys(text) {
+                if let Some(problem) = check_placeholder(key, library) {
+                    report(problem);
+                }
+            }
+
+            if let MsgStyle::Custom(name) = style {
+                if !library.theme.contains(name) {
+                    report(format!("style `{}` is not declared in theme", name));
+                }
+            }
+        }
+
+        for command in event.commands() {
+            if let Some(problem) = check_command(command, library) {
+                report(problem);
+            }
+        }
+
+        for condition in event.conditions() {
+            match condition {
+                Condition::Reputation(id, _) if library.factions.get(id).is_none() => {
+                    report(format!("unknown faction `{}`", id));
+                }
+                Condition::Member(id) if !is_sect(id, library) => {
+                    report(format!("`{}` is not a sect", id));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn check_command(command: &EventCommand, library: &Library) -> Option<String> {
+    match command {
+        EventCommand::AddBuff(id) | EventCommand::RemoveBuff(id) => {
+            if library.buffs.get(id).is_none() {
+                return Some(format!("unknown buff `{}`", id));
+            }
+        }
+        EventCommand::LearnSkill(id) => {
+            if library.skills.get(id).is_none() {
+                return Some(format!("unknown skill `{}`", id));
+            }
+        }
+        EventCommand::SetSwitch(name, _) => {
+            if !library.switches.contains(name) {
+                return Some(format!("switch `{}` is not declared", name));
+            }
+        }
+        EventCommand::SetVariable(name, _) => {
+            if !library.variables.contains(name) {
+                return Some(format!("variable `{}` is not declared", name));
+            }
+        }
+        EventCommand::GiveItem(id, count) | EventCommand::TakeItem(id, count) => {
+            if library.items.get(id).is_none() {
+                return Some(format!("unknown item `{}`", id));
+            }
+            if *count == 0 {
+                return Some(format!("no `{}` to give or take", id));
+            }
+        }
+        EventCommand::GiveExp(amount) => {
+            if *amount == 0 {
+                return Some("no experience to give".to_string());
+            }
+        }
+        EventCommand::ChangeReputation(id, delta) => {
+            if library.factions.get(id).is_none() {
+                return Some(format!("unknown faction `{}`", id));
+            }
+            if *delta == 0 {
+                return Some(format!("no reputation to change with `{}`", id));
+            }
+        }
+        EventCommand::ChangeAlignment(delta) => {
+            if *delta == 0 {
+                return Some("no alignment to change".to_string());
+            }
+        }
+        EventCommand::JoinSect(id) => {
+            if !is_sect(id, library) {
+                return Some(format!("`{}` is not a sect", id));
+            }
+        }
+        EventCommand::Injure(kind, severity) => {
+            if library.injuries.get(*kind).is_none() {
+                return Some(format!("no rule for injury `{:?}`", kind));
+            }
+            if *severity == 0 {
+                return Some("injury without severity".to_string());
+            }
+        }
+        EventCommand::StartBattle(spec) => {
+            if spec.enemies.is_empty() {
+                return Some("battle without enemies".to_string());
+            }
+            if let Some(id) = spec
+                .enemies
+                .iter()
+                .find(|id| library.enemies.get(id).is_none())
+            {
+                return Some(format!("unknown enemy `{}`", id));
+            }
+            if let Some(name) = [&spec.win, &spec.lose]
+                .into_iter()
+                .flatten()
+                .find(|name| !library.switches.contains(name))
+            {
+                return Some(format!("switch `{}` is not declared", name));
+            }
+        }
+        EventCommand::SetTile(_, _, tile) => {
+            if *tile >= library.tiles.data.len() {
+                return Some(format!("unknown tile {}", tile));
+            }
+        }
+    }
+    None
+}
+
+fn check_backgrounds(backgrounds: &Backgrounds, library: &Library, problems: &mut Vec<String>) {
+    if backgrounds.is_empty() {
+        problems.push("backgrounds: no background to choose".to_string());
+    }
+
+    let mut ids = HashSet::new();
+    for background in backgrounds.iter() {
+        if !ids.insert(&background.id) {
+            problems.push(format!("backgrounds: duplicate id `{}`", background.id));
+        }
+        for problem in [&background.name, &background.description]
+            .into_iter()
+            .filter_map(check_text)
+        {
+            problems.push(format!("backgrounds: {}: {}", background.id, problem));
+        }
+
+        for command in background.commands.iter() {
+            if let Some(problem) = check_command(command, library) {
+                problems.push(format!("backgrounds: {}: {}", background.id, problem));
+            }
+        }
+    }
+}
+
+fn check_factions(library: &Library, problems: &mut Vec<String>) {
+    let mut ids = HashSet::new();
+    for faction in library.factions.iter() {
+        if !ids.insert(&faction.id) {
+            problems.push(format!("factions: duplicate id `{}`", faction.id));
+        }
+        for problem in [&faction.name, &faction.description]
+            .into_iter()
+            .filter_map(check_text)
+        {
+            problems.push(format!("factions: {}: {}", faction.id, problem));
+        }
+    }
+}
+
+fn is_sect(id: &str, library: &Library) -> bool {
+    library.factions.get(id).is_some_and(|f| f.sect)
+}
+
+fn check_buffs(library: &Library, problems: &mut Vec<String>) {
+    let mut ids = HashSet::new();
+    for buff in library.buffs.iter() {
+        if !ids.insert(&buff.id) {
+            problems.push(format!("buffs: duplicate id `{}`", buff.id));
+        }
+        for problem in [&buff.name].into_iter().filter_map(check_text) {
+            problems.push(format!("buffs: {}: {}", buff.id, problem));
+        }
+
+        if let Some(problem) = check_modifiers(&buff.modifiers) {
+            problems.push(format!("buffs: {}: {}", buff.id, problem));
+        }
+    }
+}
+
+fn check_skills(library: &Library, problems: &mut Vec<String>) {
+    let mut ids = HashSet::new();
+    for skill in library.skills.iter() {
+        if !ids.insert(&skill.id) {
+            problems.push(format!("skills: duplicate id `{}`", skill.id));
+        }
+        for problem in [&skill.name, &skill.description]
+            .into_iter()
+            .filter_map(check_text)
+        {
+            problems.push(format!("skills: {}: {}", skill.id, problem));
+        }
+
+        if skill.max_level == 0 || skill.practice == 0 {
+            problems.push(format!(
+                "skills: {}: max_level and practice must be positive",
+                skill.id
+            ));
+        }
+
+        if skill.cost < 0.0 || skill.neili < 0.0 {
+            problems.push(format!(
+                "skills: {}: cost and neili must not be negative",
+                skill.id
+            ));
+        }
+
+        if let Some(problem) = check_modifiers(&skill.modifiers) {
+            problems.push(format!("skills: {}: {}", skill.id, problem));
+        }
+
+        if let Some(sect) = &skill.sect {
+            if !is_sect(sect, library) {
+                problems.push(format!("skills: {}: `{}` is not a sect", skill.id, sect));
+            }
+        }
+    }
+}
+
+fn check_items(library: &Library, problems: &mut Vec<String>) {
+    let mut ids = HashSet::new();
+    for item in library.items.iter() {
+        if !ids.insert(&item.id) {
+            problems.push(format!("items: duplicate id `{}`", item.id));
+        }
+        for problem in [&item.name, &item.description]
+            .into_iter()
+            .filter_map(check_text)
+        {
+            problems.push(format!("items: {}: {}", item.id, problem));
+        }
+
+        if item.stack == 0 || item.weight < 0.0 {
+            problems.push(format!(
+                "items: {}: stack must be positive and weight not negative",
+                item.id
+            ));
+        }
+
+        let equippable = EquipSlot::of(item.category).is_some();
+        if !equippable && !item.modifiers.is_empty() {
+            problems.push(format!(
+                "items: {}: only equipment can have modifiers",
+                item.id
+            ));
+        }
+        if item.weapon.is_some() != (item.category == ItemCategory::Weapon) {
+            problems.push(format!(
+                "items: {}: weapons and only weapons need a weapon kind",
+                item.id
+            ));
+        }
+        if let Some(problem) = check_modifiers(&item.modifiers) {
+            problems.push(format!("items: {}: {}", item.id, problem));
+        }
+
+        if let Some(effect) = &item.effect {
+            if effect.restore.keys().any(|p| p.max().is_none()) {
+                problems.push(format!(
+                    "items: {}: only Jing, Qi and Shen can be restored",
+                    item.id
+                ));
+            }
+            for id in effect.buffs.iter() {
+                if library.buffs.get(id).is_none() {
+                    problems.push(format!("items: {}: unknown buff `{}`", item.id, id));
+                }
+            }
+            for (kind, amount) in effect.cure.iter() {
+                if library.injuries.get(*kind).is_none() || *amount == 0 {
+                    problems.push(format!(
+                        "items: {}: cannot cure injury `{:?}`",
+                        item.id, kind
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn check_injuries(library: &Library, problems: &mut Vec<String>) {
+    let mut kinds = HashSet::new();
+    for rule in library.injuries.iter() {
+        let mut report = |problem: String| {
+            problems.push(format!("injuries: {:?}: {}", rule.kind, problem));
+        };
+
+        if !kinds.insert(rule.kind) {
+            report("duplicate rule".to_string());
+        }
+        if rule.max_severity == 0 || rule.worsen == 0 {
+            report("max_severity and worsen must be positive".to_string());
+        }
+        if rule.heal.values().any(|m| *m == 0) || rule.heal.contains_key(&Activity::Active) {
+            report("only rest can heal, and it must take some minutes".to_string());
+        }
+        if let Some(problem) = check_modifiers(&rule.modifiers) {
+            report(problem);
+        }
+    }
+}
+
+fn check_enemies(library: &Library, problems: &mut Vec<String>) {
+    let mut ids = HashSet::new();
+    for enemy in library.enemies.iter() {
+        if !ids.insert(&enemy.id) {
+            problems.push(format!("enemies: duplicate id `{}`", enemy.id));
+        }
+        for problem in [&enemy.name].into_iter().filter_map(check_text) {
+            problems.push(format!("enemies: {}: {}", enemy.id, problem));
+        }
+
+        if enemy.glyph.is_empty() || text::width(&enemy.glyph) > 2 {
+            problems.push(format!(
+                "enemies: {}: glyph must be one or two columns wide",
+                enemy.id
+            ));
+        }
+
+        for id in enemy.skills.keys() {
+            if library.skills.get(id).is_none() {
+                problems.push(format!("enemies: {}: unknown skill `{}`", enemy.id, id));
+            }
+        }
+
+        for loot in enemy.loot.iter() {
+            if library.items.get(&loot.item).is_none() {
+                problems.push(format!(
+                    "enemies: {}: unknown item `{}`",
+                    enemy.id, loot.item
+                ));
+            }
+            if !(0.0..=1.0).contains(&loot.chance) || loot.count == 0 {
+                problems.push(format!(
+                    "enemies: {}: loot `{}` needs a chance between 0 and 1 and a positive count",
+                    enemy.id, loot.item
+                ));
+            }
+        }
+    }
+}
+
+fn check_merchants(library: &Library, problems: &mut Vec<String>) {
+    if library.merchants.iter().next().is_some() && library.items.get(CURRENCY).is_none() {
+        problems.push(format!("items: currency `{}` is missing", CURRENCY));
+    }
+
+    let mut ids = HashSet::new();
+    for merchant in library.merchants.iter() {
+        let mut report = |problem: String| {
+            problems.push(format!("merchants: {}: {}", merchant.id, problem));
+        };
+
+        if !ids.insert(&merchant.id) {
+            report("duplicate id".to_string());
+        }
+        for problem in [&merchant.name].into_iter().filter_map(check_text) {
+            report(problem);
+        }
+        if merchant.glyph.is_empty() || text::width(&merchant.glyph) > 2 {
+            report("glyph must be one or two columns wide".to_string());
+        }
+        if let Some(id) = &merchant.faction {
+            if library.factions.get(id).is_none() {
+                report(format!("unknown faction `{}`", id));
+            }
+        }
+        if merchant.restock == 0 || !(0.0..=1.0).contains(&merchant.buy_ratio) {
+            report("restock must be positive and buy_ratio between 0 and 1".to_string());
+        }
+        for entry in merchant.stock.iter() {
+            match library.items.get(&entry.item) {
+                None => report(format!("unknown item `{}`", entry.item)),
+                Some(item) if item.price == 0 => {
+                    report(format!("item `{}` has no price", entry.item));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn check_recipes(recipes: &RecipeLibrary, library: &Library, problems: &mut Vec<String>) {
+    let mut ids = HashSet::new();
+    for recipe in recipes.iter() {
+        let mut report = |problem: String| {
+            problems.push(format!("recipes: {}: {}", recipe.id, problem));
+        };
+
+        if !ids.insert(&recipe.id) {
+            report("duplicate id".to_string());
+        }
+        for problem in [&recipe.name, &recipe.description]
+            .into_iter()
+            .filter_map(check_text)
+        {
+            report(problem);
+        }
+        if recipe.inputs.is_empty() || recipe.outputs.is_empty() {
+            report("needs inputs and outputs".to_string());
+        }
+        for entry in recipe.inputs.iter().chain(recipe.outputs.iter()) {
+            if library.items.get(&entry.item).is_none() {
+                report(format!("unknown item `{}`", entry.item));
+            }
+            if entry.count == 0 {
+                report(format!("count of `{}` must be positive", entry.item));
+            }
+        }
+        for id in recipe.tools.iter() {
+            if library.items.get(id).is_none() {
+                report(format!("unknown tool `{}`", id));
+            }
+        }
+        for id in recipe.skills.keys() {
+            if library.skills.get(id).is_none() {
+                report(format!("unknown skill `{}`", id));
+            }
+        }
+        if let Some(station) = &recipe.station {
+            let placed = library
+                .tiles
+                .data
+                .iter()
+                .any(|t| t.station.as_ref() == Some(station));
+            if !placed {
+                report(format!("no tile is a `{}` station", station));
+            }
+        }
+    }
+}
+
+fn check_tiles(library: &Library, problems: &mut Vec<String>) {
+    let count = library.tiles.data.len();
+    for tile in library.tiles.data.iter() {
+        if let Some(problem) = check_text(&tile.name) {
+            problems.push(format!("tiles: {}: {}", tile.name, problem));
+        }
+        if let Some(opened) = tile.opens {
+            if opened >= count || !library.tiles.data[opened].is_open() {
+                problems.push(format!(
+                    "tiles: {}: opens into {}, which is not an open tile",
+                    tile.name, opened
+                ));
+            }
+        }
+        if let Some(passage) = &tile.passage {
+            if let Some(problem) = check_passage(passage, library) {
+                problems.push(format!("tiles: {}: {}", tile.name, problem));
+            }
+        }
+    }
+}
+
+fn check_passage(passage: &Passage, library: &Library) -> Option<String> {
+    if passage.requires.is_empty() || passage.refusal.get().is_empty() {
+        return Some("a passage needs requirements and a refusal".to_string());
+    }
+    if let Some(problem) = check_text(&passage.refusal) {
+        return Some(problem);
+    }
+    passage.requires.iter().find_map(|r| match r {
+        Requirement::Switch(name) if !library.switches.contains(name) => {
+            Some(format!("switch `{}` is not declared", name))
+        }
+        Requirement::Item(id) if library.items.get(id).is_none() => {
+            Some(format!("unknown item `{}`", id))
+        }
+        Requirement::Skill(id, _) if library.skills.get(id).is_none() => {
+            Some(format!("unknown skill `{}`", id))
+        }
+        _ => None,
+    })
+}
+
+fn check_resources(resources: &ResourceLibrary, library: &Library, problems: &mut Vec<String>) {
+    let mut ids = HashSet::new();
+    for node in resources.iter() {
+        let mut report = |problem: String| {
+            problems.push(format!("resources: {}: {}", node.id, problem));
+        };
+
+        if !ids.insert(&node.id) {
+            report("duplicate id".to_string());
+        }
+        for problem in [&node.name].into_iter().filter_map(check_text) {
+            report(problem);
+        }
+        if node.respawn == 0 || node.jing < 0.0 {
+            report("respawn must be positive and jing not negative".to_string());
+        }
+        for y in node.yields.iter() {
+            if library.items.get(&y.item).is_none() {
+                report(format!("unknown item `{}`", y.item));
+            }
+            if !(0.0..=1.0).contains(&y.chance) || y.per_level < 0.0 {
+                report(format!(
+                    "yield `{}` needs a chance between 0 and 1 and per_level not negative",
+                    y.item
+                ));
+            }
+        }
+        if let Some(id) = &node.skill {
+            if library.skills.get(id).is_none() {
+                report(format!("unknown skill `{}`", id));
+            }
+        }
+        if let Some(tile) = node.leaves {
+            if tile >= library.tiles.data.len() {
+                report(format!("leaves unknown tile {}", tile));
+            }
+        }
+    }
+
+    for tile in library.tiles.data.iter() {
+        if let Some(id) = &tile.resource {
+            if resources.get(id).is_none() {
+                problems.push(format!("tiles: {}: unknown resource `{}`", tile.name, id));
+            }
+        }
+    }
+}
+
+/// Check that creatures, spawn zones and stalls are on the map and
+/// refer to known enemies and merchants.
+fn check_map(name: &str, world: &World, library: &Library, problems: &mut Vec<String>) {
+    let (width, height) = (
+        world.blocks.first().map_or(0, |r| r.len()),
+        world.blocks.len(),
+    );
+    let inside = |(x, y): (usize, usize)| x < width && y < height;
+
+    for problem in [&world.name, &world.region]
+        .into_iter()
+        .filter_map(check_text)
+    {
+        problems.push(format!("{}: {}", name, problem));
+    }
+
+    for creature in world.creatures.iter() {
+        if library.enemies.get(&creature.enemy).is_none() {
+            problems.push(format!("{}: unknown enemy `{}`", name, creature.enemy));
+        }
+        if !inside((creature.x, creature.y)) {
+            problems.push(format!(
+                "{}: {} at ({}, {}) is outside the map",
+                name, creature.enemy, creature.x, creature.y
+            ));
+        }
+    }
+
+    for (i, zone) in world.spawns.iter().enumerate() {
+        let mut report = |problem: String| {
+            problems.push(format!("{}: spawn zone {}: {}", name, i, problem));
+        };
+
+        if zone.enemies.is_empty() {
+            report("no enemies to spawn".to_string());
+        }
+        for id in zone.enemies.iter() {
+            if library.enemies.get(id).is_none() {
+                report(format!("unknown enemy `{}`", id));
+            }
+        }
+        if !inside(zone.area.to) || zone.area.is_empty() {
+            report("`from` and `to` must be the corners of an area on the map".to_string());
+        }
+        if !(0.0..=1.0).contains(&zone.chance) {
+            report("chance must be between 0 and 1".to_string());
+        }
+    }
+
+    for stall in world.stalls.iter() {
+        if library.merchants.get(&stall.merchant).is_none() {
+            problems.push(format!("{}: unknown merchant `{}`", name, stall.merchant));
+        }
+        if !inside((stall.x, stall.y)) {
+            problems.push(format!(
+                "{}: {} at ({}, {}) is outside the map",
+                name, stall.merchant, stall.x, stall.y
+            ));
+        }
+    }
+
+    for (i, barrier) in world.barriers.iter().enumerate() {
+        if !inside(barrier.area.to) || barrier.area.is_empty() {
+            problems.push(format!(
+                "{}: barrier {}: `from` and `to` must be the corners of an area on the map",
+                name, i
+            ));
+        }
+        if let Some(problem) = check_passage(&barrier.passage, library) {
+            problems.push(format!("{}: barrier {}: {}", name, i, problem));
+        }
+    }
+
+    if let Some(spawn) = world.spawn {
+        if !inside(spawn) {
+            problems.push(format!("{}: spawn is outside the map", name));
+        }
+    }
+}
+
+fn check_defeat(defeat: &Defeat, library: &Library, problems: &mut Vec<String>) {
+    let mut report = |problem: String| problems.push(format!("defeat: {}", problem));
+
+    for (i, rule) in defeat.rules.iter().enumerate() {
+        let event = match &rule.outcome {
+            DefeatOutcome::Event(switch) => Some(switch),
+            _ => None,
+        };
+        for name in rule.switch.iter().chain(rule.unless.iter()).chain(event) {
+            if !library.switches.contains(name) {
+                report(format!("rule {}: switch `{}` is not declared", i, name));
+            }
+        }
+    }
+
+    let faint = &defeat.faint;
+    if !(0.0..=1.0).contains(&faint.recover) || !(0.0..=1.0).contains(&faint.exp_loss) {
+        report("faint: recover and exp_loss must be between 0 and 1".to_string());
+    }
+    for (id, ratio) in faint.items.iter() {
+        if library.items.get(id).is_none() || !(0.0..=1.0).contains(ratio) {
+            report(format!("faint: cannot lose item `{}`", id));
+        }
+    }
+    for (kind, severity) in faint.injuries.iter() {
+        if library.injuries.get(*kind).is_none() || *severity == 0 {
+            report(format!("faint: cannot suffer injury `{:?}`", kind));
+        }
+    }
+}
+
+fn check_modifiers(modifiers: &[Modifier]) -> Option<String> {
+    modifiers
+        .iter()
+        .any(|m| {
+            matches!(
+                m.target,
+                ModTarget::Prop(PropertyType::Jing | PropertyType::Qi | PropertyType::Shen)
+            )
+        })
+        .then(|| "only maxima of properties can be modified".to_string())
+}
+
+/// Check that every maximum has a formula which only uses known
+/// variables and can be evaluated.
+fn check_formulas(library: &Library, problems: &mut Vec<String>) {
+    for prop in PropertyType::CURRENT.iter().filter_map(|p| p.max()) {
+        if library.formulas.get(&prop).is_none() {
+            problems.push(format!("formulas: missing formula for `{:?}`", prop));
+        }
+    }
+
+    for (prop, formula) in library.formulas.iter() {
+        let unknown: Vec<&str> = formula
+            .variables()
+            .into_iter()
+            .filter(|v| !is_formula_var(v, library))
+            .collect();
+        for name in unknown.iter() {
+            problems.push(format!("formulas: {:?}: unknown variable `{}`", prop, name));
+        }
+
+        if unknown.is_empty() {
+            // try with an average character at level 1
+            let value = formula.eval(&|name| Some(if name == "level" { 1.0 } else { 10.0 }));
+            match value {
+                Ok(v) if !v.is_finite() || v < 0.0 => problems.push(format!(
+                    "formulas: {:?}: `{}` gives {} for an average character",
+                    prop, formula, v
+                )),
+                Ok(_) => {}
+                Err(e) => problems.push(format!("formulas: {:?}: {}", prop, e)),
+            }
+        }
+    }
+}
+
+/// Whether formulas about the player can use a variable.
+fn is_formula_var(name: &str, library: &Library) -> bool {
+    let attr = name.strip_prefix("base.").unwrap_or(name);
+    name == "level"
+        || name == "inner"
+        || AttributeType::from_key(attr).is_some()
+        || name
+            .strip_prefix("buff.")
+            .is_some_and(|id| library.buffs.get(id).is_some())
+        || name
+            .strip_prefix("skill.")
+            .is_some_and(|id| library.skills.get(id).is_some())
+}
+
+/// Check that regeneration only applies to current properties and its
+/// formulas only use known variables.
+fn check_regen(regen: &Regen, library: &Library, problems: &mut Vec<String>) {
+    for (activity, prop, formula) in regen.iter() {
+        let mut report = |problem: String| {
+            problems.push(format!("regen: {:?}: {:?}: {}", activity, prop, problem));
+        };
+
+        if prop.max().is_none() && *prop != PropertyType::MaxNeili {
+            report("only current properties and MaxNeili regenerate".to_string());
+        }
+        for name in formula.variables() {
+            if name != "night" && !is_formula_var(name, library) {
+                report(format!("unknown variable `{}`", name));
+            }
+        }
+    }
+}
+
+/// Check that every language has all strings of the fallback language.
+fn check_locales(cache: &AssetCache, problems: &mut Vec<String>) {
+    let mut locales = vec![];
+    for (language, locale) in locale::load_all(cache) {
+        match locale {
+            Ok(locale) => locales.push((language, locale)),
+            Err(e) => problems.push(format!("locales: {}: {}", language, e)),
+        }
+    }
+
+    let fallback = match locales.iter().find(|(l, _)| l == FALLBACK_LANGUAGE) {
+        Some((_, locale)) => locale,
+        None => {
+            problems.push(format!("locales: missing `{}`", FALLBACK_LANGUAGE));
+            return;
+        }
+    };
+
+    for (language, locale) in locales.iter() {
+        for key in fallback.keys() {
+            if locale.get(key).is_none() {
+                problems.push(format!("locales: {}: missing string `{}`", language, key));
+            }
+        }
+    }
+}
+
+/// A localized text must be written in the fallback language, which is
+/// shown when the selected one is missing.
+fn check_text(text: &Text) -> Option<String> {
+    if text.languages()?.any(|l| l == FALLBACK_LANGUAGE) {
+        None
+    } else {
+        Some(format!("`{}` has no `{}` text", text, FALLBACK_LANGUAGE))
+    }
+}
+
+fn check_placeholder(key: &str, library: &Library) -> Option<String> {
+    match Placeholder::parse(key) {
+        None => Some(format!("unknown placeholder `{{{}}}`", key)),
+        Some(Placeholder::Var(name)) if !library.variables.contains(&name) => {
+            Some(format!("undeclared variable in `{{{}}}`", key))
+        }
+        Some(Placeholder::Standing(id)) if library.factions.get(&id).is_none() => {
+            Some(format!("unknown faction in `{{{}}}`", key))
+        }
+        Some(_) => None,
+    }
+}
+
+/// Describe an error together with all its causes.
+fn describe(error: &dyn Error) -> String {
+    let mut output = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        output.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    output
+}
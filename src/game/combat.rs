@@ -0,0 +1,535 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::components::player::Player;
+
+use super::{
+    apply_modifiers,
+    locale::{trf, Text},
+    AiProfile, Attribute, AttributeType, Enemy, InjuryKind, LearnedSkill, ModTarget, PropertyType,
+    Rules, Skill, SkillKind, SkillLibrary, Skills,
+};
+
+const BASE_HIT: f64 = 0.75;
+const MIN_HIT: f64 = 0.1;
+const MAX_HIT: f64 = 0.95;
+/// Damage taken while defending is multiplied by this.
+const DEFEND_FACTOR: f64 = 0.5;
+/// Jing below this part of the maximum makes cautious foes defend and
+/// timid foes run.
+const HURT_RATIO: f64 = 0.3;
+/// A blow getting through Qi and taking at least this part of the
+/// player's maximum Jing inflicts an injury.
+const WOUND_RATIO: f64 = 0.25;
+/// Practice gained each time a skill is used in battle.
+const BATTLE_PRACTICE: u32 = 1;
+
+/// A battle started by an event, e.g.
+/// `{ "StartBattle": { "enemies": ["boar"], "win": "boar_defeated" } }`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BattleSpec {
+    /// Enemy ids.
+    pub enemies: Vec<String>,
+    /// Switch turned on when the player wins.
+    #[serde(default)]
+    pub win: Option<String>,
+    /// Switch turned on when the player loses.
+    #[serde(default)]
+    pub lose: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Side {
+    Player,
+    Foe(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Victory,
+    Defeat,
+    Fled,
+}
+
+/// What the player does on their turn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Attack,
+    Defend,
+    /// Attack with a learned skill.
+    Skill(String),
+    /// Use an item of an inventory slot.
+    Item(usize),
+    Flee,
+}
+
+/// An enemy taking part in a battle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Foe {
+    /// Enemy id.
+    pub id: String,
+    pub name: Text,
+    ai: AiProfile,
+    attr: Attribute,
+    skills: Skills,
+    pub jing: f64,
+    pub max_jing: f64,
+    pub qi: f64,
+    pub max_qi: f64,
+    pub shen: f64,
+    pub max_shen: f64,
+    defending: bool,
+    /// Ran away from the battle.
+    escaped: bool,
+}
+
+impl Foe {
+    /// Make a foe of an enemy. Fail if a formula of its maxima fails.
+    pub fn new(enemy: &Enemy, rules: &Rules) -> Result<Foe, String> {
+        let mut skills = Skills::default();
+        for (id, level) in enemy.skills.iter() {
+            if let Some(skill) = rules.skills.get(id) {
+                skills.learn_at(skill, *level);
+            }
+        }
+
+        let mut attr = enemy.attr.clone();
+        for t in AttributeType::ALL {
+            let value = apply_modifiers(
+                enemy.attr.get(t) as f64,
+                ModTarget::Attr(t),
+                skills.modifiers(&rules.skills),
+            );
+            attr.set(t, value.round().max(0.0) as usize);
+        }
+
+        let vars = |name: &str| -> Option<f64> {
+            if name == "level" {
+                Some(enemy.level as f64)
+            } else if let Some(id) = name.strip_prefix("skill.") {
+                Some(skills.level(id) as f64)
+            } else if name.starts_with("buff.") {
+                Some(0.0)
+            } else if let Some(key) = name.strip_prefix("base.") {
+                AttributeType::from_key(key).map(|t| enemy.attr.get(t) as f64)
+            } else {
+                AttributeType::from_key(name).map(|t| attr.get(t) as f64)
+            }
+        };
+        let max = |prop: PropertyType| {
+            let formula = rules
+                .formulas
+                .get(&prop)
+                .ok_or_else(|| format!("{:?}: no formula", prop))?;
+            let base = formula
+                .eval(&vars)
+                .map_err(|e| format!("{:?}: {} in `{}`", prop, e, formula))?;
+            Ok::<f64, String>(
+                apply_modifiers(base, ModTarget::Prop(prop), skills.modifiers(&rules.skills))
+                    .max(1.0),
+            )
+        };
+        let (max_jing, max_qi, max_shen) = (
+            max(PropertyType::MaxJing)?,
+            max(PropertyType::MaxQi)?,
+            max(PropertyType::MaxShen)?,
+        );
+
+        Ok(Foe {
+            id: enemy.id.clone(),
+            name: enemy.name.clone(),
+            ai: enemy.ai,
+            attr,
+            skills,
+            jing: max_jing,
+            max_jing,
+            qi: max_qi,
+            max_qi,
+            shen: max_shen,
+            max_shen,
+            defending: false,
+            escaped: false,
+        })
+    }
+
+    /// Out of the fight, beaten or run away.
+    pub fn is_down(&self) -> bool {
+        self.is_beaten() || self.escaped
+    }
+
+    pub fn is_beaten(&self) -> bool {
+        self.jing <= 0.0
+    }
+
+    fn fighter(&self, library: &SkillLibrary) -> Fighter {
+        Fighter {
+            attr: self.attr.clone(),
+            shen_ratio: self.shen / self.max_shen,
+            evasion: self.skills.total_level(SkillKind::Qinggong, library),
+            defending: self.defending,
+        }
+    }
+
+    /// Take damage on Qi first, then on Jing.
+    fn take_damage(&mut self, damage: f64) {
+        let (qi_loss, jing_loss) = split_damage(self.qi, damage);
+        self.qi -= qi_loss;
+        self.jing = (self.jing - jing_loss).max(0.0);
+    }
+}
+
+/// What decides the result of an attack.
+struct Fighter {
+    /// Effective attributes.
+    attr: Attribute,
+    /// Current Shen over maximum Shen. Tired fighters miss more.
+    shen_ratio: f64,
+    /// Levels of qinggong, which make a fighter harder to hit.
+    evasion: u32,
+    defending: bool,
+}
+
+impl Fighter {
+    fn of_player(player: &Player, defending: bool, library: &SkillLibrary) -> Fighter {
+        let prop = player.prop();
+        let max_shen = prop.number(&PropertyType::MaxShen);
+        Fighter {
+            attr: player.effective_attr(),
+            shen_ratio: if max_shen > 0.0 {
+                prop.number(&PropertyType::Shen) / max_shen
+            } else {
+                0.0
+            },
+            evasion: player.skills().total_level(SkillKind::Qinggong, library),
+            defending,
+        }
+    }
+
+    fn get(&self, t: AttributeType) -> f64 {
+        self.attr.get(t) as f64
+    }
+
+    fn hit_chance(&self, target: &Fighter) -> f64 {
+        let chance = BASE_HIT
+            + (self.shen_ratio - 0.5) * 0.4
+            + (self.get(AttributeType::Dex) - target.get(AttributeType::Dex)) * 0.02
+            - target.evasion as f64 * 0.01;
+        chance.clamp(MIN_HIT, MAX_HIT)
+    }
+
+    /// Roll an attack. Return the damage, or None if it missed.
+    fn strike(&self, target: &Fighter, power: f64) -> Option<f64> {
+        let mut rng = rand::thread_rng();
+        if !rng.gen_bool(self.hit_chance(target)) {
+            return None;
+        }
+
+        let damage = (self.get(AttributeType::Str) + power) * rng.gen_range(0.8..1.2)
+            - target.get(AttributeType::Con) / 4.0;
+        let damage = if target.defending {
+            damage * DEFEND_FACTOR
+        } else {
+            damage
+        };
+        Some(damage.max(1.0).round())
+    }
+}
+
+/// Split damage into the part taken by Qi and the rest taken by Jing.
+fn split_damage(qi: f64, damage: f64) -> (f64, f64) {
+    let qi_loss = damage.min(qi.max(0.0));
+    (qi_loss, damage - qi_loss)
+}
+
+/// Describe an attack for the battle log.
+fn report(attacker: &str, target: &str, skill: Option<&str>, damage: Option<f64>) -> String {
+    let attack = match skill {
+        Some(skill) => trf("battle.use_skill", &[attacker, skill, target]),
+        None => trf("battle.attack", &[attacker, target]),
+    };
+    let result = match damage {
+        Some(d) => trf("battle.hit", &[target, &d.to_string()]),
+        None => trf("battle.miss", &[target]),
+    };
+    format!("{}{}", attack, result)
+}
+
+/// A battle between the player and a group of foes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Battle {
+    pub foes: Vec<Foe>,
+    spec: BattleSpec,
+    /// Who acts this round, fastest first.
+    order: Vec<Side>,
+    turn: usize,
+    pub round: u32,
+    player_defending: bool,
+    /// Foe attacked by the player.
+    pub target: usize,
+    fled: bool,
+    /// Position of the creature fought on the map.
+    #[serde(default)]
+    pub creature: Option<(usize, usize)>,
+}
+
+impl Battle {
+    pub fn new(spec: BattleSpec, foes: Vec<Foe>, player: &Player) -> Battle {
+        let mut battle = Battle {
+            foes,
+            spec,
+            order: vec![],
+            turn: 0,
+            round: 0,
+            player_defending: false,
+            target: 0,
+            fled: false,
+            creature: None,
+        };
+        battle.start_round(player);
+        battle
+    }
+
+    pub fn spec(&self) -> &BattleSpec {
+        &self.spec
+    }
+
+    /// Order everyone still standing by dexterity. The player goes first
+    /// on ties.
+    fn start_round(&mut self, player: &Player) {
+        let player_dex = player.effective_attr().get(AttributeType::Dex);
+        let mut order: Vec<(Side, usize)> = vec![(Side::Player, player_dex)];
+        for (i, foe) in self.foes.iter().enumerate() {
+            if !foe.is_down() {
+                order.push((Side::Foe(i), foe.attr.get(AttributeType::Dex)));
+            }
+        }
+        order.sort_by_key(|(_, dex)| std::cmp::Reverse(*dex));
+
+        self.order = order.into_iter().map(|(side, _)| side).collect();
+        self.turn = 0;
+        self.round += 1;
+    }
+
+    /// Who acts now.
+    pub fn current(&self) -> Side {
+        self.order.get(self.turn).copied().unwrap_or(Side::Player)
+    }
+
+    fn advance(&mut self, player: &Player) {
+        loop {
+            self.turn += 1;
+            if self.turn >= self.order.len() {
+                self.start_round(player);
+            }
+
+            match self.current() {
+                Side::Foe(i) if self.foes[i].is_down() => continue,
+                Side::Foe(i) => self.foes[i].defending = false,
+                Side::Player => self.player_defending = false,
+            }
+            return;
+        }
+    }
+
+    pub fn outcome(&self, player: &Player) -> Option<Outcome> {
+        if self.fled {
+            Some(Outcome::Fled)
+        } else if player.prop().number(&PropertyType::Jing) <= 0.0 {
+            Some(Outcome::Defeat)
+        } else if self.foes.iter().all(|f| f.is_down()) {
+            Some(Outcome::Victory)
+        } else {
+            None
+        }
+    }
+
+    /// Select the next (or previous) foe still standing.
+    pub fn cycle_target(&mut self, forward: bool) {
+        let len = self.foes.len();
+        for step in 1..=len {
+            let i = if forward {
+                (self.target + step) % len
+            } else {
+                (self.target + len * step - step) % len
+            };
+            if !self.foes[i].is_down() {
+                self.target = i;
+                return;
+            }
+        }
+    }
+
+    /// Carry out the player's action. Return lines for the battle log,
+    /// and whether the turn was used.
+    pub fn player_act(
+        &mut self,
+        action: &Action,
+        player: &mut Player,
+        rules: &Rules,
+    ) -> (Vec<String>, bool) {
+        if self.foes.get(self.target).is_none_or(|f| f.is_down()) {
+            self.cycle_target(true);
+        }
+
+        let mut lines = vec![];
+        match action {
+            Action::Attack => {
+                let foe = &mut self.foes[self.target];
+                let damage = Fighter::of_player(player, false, &rules.skills)
+                    .strike(&foe.fighter(&rules.skills), 0.0);
+                lines.push(report(player.name(), foe.name.get(), None, damage));
+                foe.take_damage(damage.unwrap_or(0.0));
+            }
+            Action::Defend => {
+                self.player_defending = true;
+                lines.push(trf("battle.defend", &[player.name()]));
+            }
+            Action::Skill(id) => {
+                let (level, skill) = match player.skills().get(id).zip(rules.skills.get(id)) {
+                    Some((learned, skill)) => (learned.level, skill),
+                    None => return (lines, false),
+                };
+                if !skill.kind.allows(player.equipment().weapon(&rules.items)) {
+                    lines.push(trf("battle.wrong_weapon", &[skill.name.get()]));
+                    return (lines, false);
+                }
+                let qi = player.prop().number(&PropertyType::Qi);
+                if qi < skill.cost {
+                    lines.push(trf("battle.no_qi", &[skill.name.get()]));
+                    return (lines, false);
+                }
+                let neili = player.prop().number(&PropertyType::Neili);
+                if neili < skill.neili {
+                    lines.push(trf("battle.no_neili", &[skill.name.get()]));
+                    return (lines, false);
+                }
+                player.change_prop(PropertyType::Qi, -skill.cost);
+                player.change_prop(PropertyType::Neili, -skill.neili);
+
+                let foe = &mut self.foes[self.target];
+                let power = skill.power * level as f64;
+                let damage = Fighter::of_player(player, false, &rules.skills)
+                    .strike(&foe.fighter(&rules.skills), power);
+                lines.push(report(
+                    player.name(),
+                    foe.name.get(),
+                    Some(skill.name.get()),
+                    damage,
+                ));
+                foe.take_damage(damage.unwrap_or(0.0));
+
+                if player.practice_skill(id, BATTLE_PRACTICE, rules) > 0 {
+                    lines.push(trf(
+                        "skill.level_up",
+                        &[skill.name.get(), &player.skills().level(id).to_string()],
+                    ));
+                }
+            }
+            // items are used by the game state, see `skip_turn`
+            Action::Item(_) => return (lines, false),
+            Action::Flee => {
+                let standing: Vec<&Foe> = self.foes.iter().filter(|f| !f.is_down()).collect();
+                let foe_dex = standing
+                    .iter()
+                    .map(|f| f.attr.get(AttributeType::Dex) as f64)
+                    .sum::<f64>()
+                    / standing.len().max(1) as f64;
+                let player_dex = player.effective_attr().get(AttributeType::Dex) as f64;
+                let chance = (0.5 + (player_dex - foe_dex) * 0.03).clamp(0.1, 0.9);
+
+                self.fled = rand::thread_rng().gen_bool(chance);
+                lines.push(trf(
+                    if self.fled {
+                        "battle.fled"
+                    } else {
+                        "battle.flee_failed"
+                    },
+                    &[player.name()],
+                ));
+            }
+        }
+
+        if let Some(foe) = self.foes.get(self.target).filter(|f| f.is_beaten()) {
+            lines.push(trf("battle.foe_down", &[foe.name.get()]));
+        }
+
+        self.advance(player);
+        (lines, true)
+    }
+
+    /// End the turn of the player without acting in the battle.
+    pub fn skip_turn(&mut self, player: &Player) {
+        self.advance(player);
+    }
+
+    /// Let foes act until it is the player's turn or the battle is over.
+    pub fn foes_act(&mut self, player: &mut Player, rules: &Rules) -> Vec<String> {
+        let mut lines = vec![];
+
+        while self.outcome(player).is_none() {
+            let i = match self.current() {
+                Side::Player => break,
+                Side::Foe(i) => i,
+            };
+
+            let foe = &mut self.foes[i];
+            let hurt = foe.jing < foe.max_jing * HURT_RATIO;
+            let mut rng = rand::thread_rng();
+            if hurt && foe.ai == AiProfile::Timid && rng.gen_bool(0.5) {
+                foe.escaped = true;
+                lines.push(trf("battle.foe_fled", &[foe.name.get()]));
+            } else if hurt && foe.ai == AiProfile::Cautious && rng.gen_bool(0.5) {
+                foe.defending = true;
+                lines.push(trf("battle.defend", &[foe.name.get()]));
+            } else {
+                // use the strongest attack the foe can afford
+                let power =
+                    |(learned, skill): &(&LearnedSkill, &Skill)| skill.power * learned.level as f64;
+                let attack = foe
+                    .skills
+                    .attacks(&rules.skills)
+                    .filter(|(_, skill)| skill.cost <= foe.qi)
+                    .max_by(|a, b| power(a).total_cmp(&power(b)));
+                let (skill, power) = match attack {
+                    Some(attack) => (Some(attack.1), power(&attack)),
+                    None => (None, 0.0),
+                };
+                if let Some(skill) = skill {
+                    foe.qi -= skill.cost;
+                }
+
+                let damage = foe.fighter(&rules.skills).strike(
+                    &Fighter::of_player(player, self.player_defending, &rules.skills),
+                    power,
+                );
+                lines.push(report(
+                    foe.name.get(),
+                    player.name(),
+                    skill.map(|s| s.name.get()),
+                    damage,
+                ));
+
+                let qi = player.prop().number(&PropertyType::Qi);
+                let (qi_loss, jing_loss) = split_damage(qi, damage.unwrap_or(0.0));
+                player.change_prop(PropertyType::Qi, -qi_loss);
+                player.change_prop(PropertyType::Jing, -jing_loss);
+
+                let max_jing = player.prop().number(&PropertyType::MaxJing);
+                let wound = skill.map_or(InjuryKind::External, |s| s.wound);
+                if jing_loss > 0.0 && jing_loss >= max_jing * WOUND_RATIO {
+                    if let Some(rule) = rules.injuries.get(wound) {
+                        let severity = player.injure(rule, 1, rules);
+                        lines.push(trf(
+                            "battle.wounded",
+                            &[player.name(), &wound.to_string(), &severity.to_string()],
+                        ));
+                    }
+                }
+            }
+
+            self.advance(player);
+        }
+
+        lines
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use assets_manager::{loader, Asset, AssetCache};
+use serde::Deserialize;
+
+use super::{GameSwitch, InjuryKind};
+
+/// What happens when the player is beaten.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub enum DefeatOutcome {
+    /// Faint and wake up at a safe place with penalties.
+    #[default]
+    Faint,
+    /// Turn a switch on, so that an event tells what happens next. The
+    /// player comes to where they fell, without penalties.
+    Event(String),
+    /// Die and go back to the dashboard.
+    Death,
+}
+
+/// An outcome chosen by switches and injuries, e.g.
+/// `{ "switch": ["bg_hunter"], "unless": ["boar_defeated"], "outcome": { "Event": "boar_lost" } }`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DefeatRule {
+    /// Switches that must be on.
+    #[serde(default)]
+    pub switch: Vec<String>,
+    /// Switches that must be off.
+    #[serde(default)]
+    pub unless: Vec<String>,
+    /// Least severity of the worst injury.
+    #[serde(default)]
+    pub severity: u32,
+    pub outcome: DefeatOutcome,
+}
+
+/// Penalties of fainting.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Faint {
+    /// Minutes spent unconscious.
+    pub minutes: u32,
+    /// Part of the maximum Jing, Qi and Shen the player wakes up with.
+    pub recover: f64,
+    /// Part of the experience gained since the last level that is lost.
+    #[serde(default)]
+    pub exp_loss: f64,
+    /// Part of the items with an id that is lost.
+    #[serde(default)]
+    pub items: HashMap<String, f64>,
+    /// Injuries suffered in the fall.
+    #[serde(default)]
+    pub injuries: HashMap<InjuryKind, u32>,
+}
+
+/// How defeats end, loaded from `assets/defeat.json`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Defeat {
+    /// Rules tried in order. The first that applies decides the outcome.
+    #[serde(default)]
+    pub rules: Vec<DefeatRule>,
+    /// Outcome when no rule applies.
+    #[serde(default)]
+    pub default: DefeatOutcome,
+    pub faint: Faint,
+}
+
+impl Asset for Defeat {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+impl Defeat {
+    pub fn load(cache: &AssetCache) -> Defeat {
+        let defeat_file = "defeat";
+        let handle = cache.load_expect::<Defeat>(defeat_file);
+
+        handle.read().to_owned()
+    }
+
+    /// Outcome of a defeat with some switches on and the severity of the
+    /// worst injury.
+    pub fn outcome(&self, switches: &GameSwitch, severity: u32) -> &DefeatOutcome {
+        self.rules
+            .iter()
+            .find(|r| {
+                switches.is_all_on(&r.switch)
+                    && !r.unless.iter().any(|s| switches.is_on(s))
+                    && severity >= r.severity
+            })
+            .map_or(&self.default, |r| &r.outcome)
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use assets_manager::{loader, Asset, AssetCache};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::{locale::Text, Attribute};
+
+/// An opponent in battle, defined in `assets/enemies.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Enemy {
+    pub id: String,
+    pub name: Text,
+    /// Symbol shown on the map.
+    pub glyph: String,
+    #[serde(default = "default_level")]
+    pub level: u32,
+    pub attr: Attribute,
+    /// Skill ids and their levels.
+    #[serde(default)]
+    pub skills: HashMap<String, u32>,
+    #[serde(default)]
+    pub ai: AiProfile,
+    #[serde(default)]
+    pub loot: Vec<Loot>,
+    /// Experience for beating it.
+    #[serde(default)]
+    pub exp: u32,
+}
+
+fn default_level() -> u32 {
+    1
+}
+
+impl Enemy {
+    /// Roll the loot table. Return item ids and counts.
+    pub fn roll_loot(&self) -> Vec<(String, u32)> {
+        let mut rng = rand::thread_rng();
+        self.loot
+            .iter()
+            .filter(|l| rng.gen_bool(l.chance.clamp(0.0, 1.0)))
+            .map(|l| (l.item.clone(), l.count))
+            .collect()
+    }
+}
+
+/// How an enemy behaves in battle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AiProfile {
+    /// Always attacks with its strongest skill.
+    #[default]
+    Aggressive,
+    /// Defends more often when hurt.
+    Cautious,
+    /// Runs away when hurt.
+    Timid,
+}
+
+/// An item dropped by a defeated enemy.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Loot {
+    pub item: String,
+    #[serde(default = "default_count")]
+    pub count: u32,
+    /// Chance between 0 and 1 to drop the item.
+    #[serde(default = "default_chance")]
+    pub chance: f64,
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+fn default_chance() -> f64 {
+    1.0
+}
+
+/// All enemy definitions.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EnemyLibrary {
+    enemies: Vec<Enemy>,
+}
+
+impl Asset for EnemyLibrary {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+impl EnemyLibrary {
+    pub fn load(cache: &AssetCache) -> EnemyLibrary {
+        let enemy_file = "enemies";
+        let handle = cache.load_expect::<EnemyLibrary>(enemy_file);
+
+        handle.read().to_owned()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Enemy> {
+        self.enemies.iter().find(|e| e.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Enemy> {
+        self.enemies.iter()
+    }
+}
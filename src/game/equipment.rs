@@ -0,0 +1,102 @@
+use std::{collections::HashMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use super::{locale::tr, ItemCategory, ItemLibrary, Modifier};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Hash, PartialEq, Eq)]
+pub enum EquipSlot {
+    Weapon,
+    Armor,
+    Accessory,
+}
+
+impl EquipSlot {
+    pub const ALL: [EquipSlot; 3] = [EquipSlot::Weapon, EquipSlot::Armor, EquipSlot::Accessory];
+
+    /// Slot an item of a category goes in, if it can be equipped.
+    pub fn of(category: ItemCategory) -> Option<EquipSlot> {
+        match category {
+            ItemCategory::Weapon => Some(EquipSlot::Weapon),
+            ItemCategory::Armor => Some(EquipSlot::Armor),
+            ItemCategory::Accessory => Some(EquipSlot::Accessory),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for EquipSlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            tr(match self {
+                EquipSlot::Weapon => "equip.slot.weapon",
+                EquipSlot::Armor => "equip.slot.armor",
+                EquipSlot::Accessory => "equip.slot.accessory",
+            })
+        )
+    }
+}
+
+/// Kind of a weapon, which decides the martial arts it can be used with.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum WeaponKind {
+    Sword,
+    Blade,
+    Staff,
+}
+
+impl fmt::Display for WeaponKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            tr(match self {
+                WeaponKind::Sword => "equip.weapon.sword",
+                WeaponKind::Blade => "equip.weapon.blade",
+                WeaponKind::Staff => "equip.weapon.staff",
+            })
+        )
+    }
+}
+
+/// Ids of the items worn by a character, one per slot.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Equipment(HashMap<EquipSlot, String>);
+
+impl Equipment {
+    /// Put on an item. Return the id of the item it replaces.
+    pub fn equip(&mut self, slot: EquipSlot, id: String) -> Option<String> {
+        self.0.insert(slot, id)
+    }
+
+    pub fn unequip(&mut self, slot: EquipSlot) -> Option<String> {
+        self.0.remove(&slot)
+    }
+
+    /// Id of the item worn in a slot.
+    pub fn get(&self, slot: EquipSlot) -> Option<&str> {
+        self.0.get(&slot).map(String::as_str)
+    }
+
+    /// Kind of the weapon held, None if bare-handed.
+    pub fn weapon(&self, library: &ItemLibrary) -> Option<WeaponKind> {
+        self.get(EquipSlot::Weapon)
+            .and_then(|id| library.get(id))
+            .and_then(|i| i.weapon)
+    }
+
+    /// All modifiers of equipped items. Each applies once. Items no
+    /// longer defined are left out.
+    pub fn modifiers<'a>(
+        &'a self,
+        library: &'a ItemLibrary,
+    ) -> impl Iterator<Item = (&'a Modifier, u32)> + Clone {
+        EquipSlot::ALL
+            .iter()
+            .filter_map(|s| self.0.get(s))
+            .filter_map(|id| library.get(id))
+            .flat_map(|i| i.modifiers.iter().map(|m| (m, 1)))
+    }
+}
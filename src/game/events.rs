@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     components::message::{MessageSystem, Msg},
-    game::GameSwitch,
+    game::{BattleSpec, Condition, GameSwitch, InjuryKind, Reputation, Value},
 };
 
 #[derive(Deserialize, Clone, Hash, Eq, PartialEq)]
@@ -11,18 +11,43 @@ pub enum EventActivation {
     Touch,
 }
 
-#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug, Default)]
 pub enum EventStage {
+    #[default]
     Waiting,
     Ready,
     Running,
     Closing,
 }
 
-impl Default for EventStage {
-    fn default() -> Self {
-        EventStage::Waiting
-    }
+/// An action run on the game state when an event runs.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub enum EventCommand {
+    AddBuff(String),
+    RemoveBuff(String),
+    /// Turn a switch on or off.
+    SetSwitch(String, bool),
+    /// Change the value of a variable, e.g. `{ "SetVariable": ["rabbits", 0] }`.
+    SetVariable(String, Value),
+    LearnSkill(String),
+    StartBattle(BattleSpec),
+    /// Give the player some items.
+    GiveItem(String, u32),
+    /// Take some items from the player.
+    TakeItem(String, u32),
+    /// Give the player experience.
+    GiveExp(u32),
+    /// Injure the player with some severity.
+    Injure(InjuryKind, u32),
+    /// Change the reputation with a faction.
+    ChangeReputation(String, i32),
+    /// Move the alignment toward 正 (positive) or 邪 (negative).
+    ChangeAlignment(i32),
+    /// Join a sect and learn its martial arts.
+    JoinSect(String),
+    /// Replace the tile at x, y of the current map with a tile index,
+    /// e.g. to break a wall. The change is kept in the save.
+    SetTile(usize, usize, usize),
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -30,7 +55,12 @@ pub struct GameEvent {
     id: usize,
     stage: EventStage,
     switch: Vec<String>,
+    /// Reputation the player needs besides the switches.
+    #[serde(default)]
+    conditions: Vec<Condition>,
     messages: Vec<Msg>,
+    #[serde(default)]
+    commands: Vec<EventCommand>,
 }
 
 impl std::hash::Hash for GameEvent {
@@ -48,8 +78,25 @@ impl PartialEq for GameEvent {
 impl Eq for GameEvent {}
 
 impl GameEvent {
-    pub fn ready(&mut self, g_switch: &GameSwitch) {
-        if g_switch.is_all_on(&self.switch) {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn messages(&self) -> &[Msg] {
+        &self.messages
+    }
+
+    pub fn commands(&self) -> &[EventCommand] {
+        &self.commands
+    }
+
+    pub fn conditions(&self) -> &[Condition] {
+        &self.conditions
+    }
+
+    pub fn ready(&mut self, g_switch: &GameSwitch, reputation: &Reputation) {
+        if g_switch.is_all_on(&self.switch) && self.conditions.iter().all(|c| c.is_met(reputation))
+        {
             self.stage = EventStage::Ready;
         }
     }
@@ -58,7 +105,7 @@ impl GameEvent {
         // Only run the event on ready stage.
         if self.stage == EventStage::Ready {
             self.stage = EventStage::Running;
-            msg_system.add_sentences(self.messages.clone());
+            msg_system.queue_sentences(self.messages.clone());
         }
     }
 }
@@ -82,6 +129,10 @@ impl EventSystem {
         handle.read().to_owned()
     }
 
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
     pub fn get_ready(&mut self) -> Vec<&mut GameEvent> {
         self.events
             .iter_mut()
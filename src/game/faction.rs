@@ -0,0 +1,200 @@
+use std::{collections::HashMap, fmt};
+
+use assets_manager::{loader, Asset, AssetCache};
+use serde::{Deserialize, Serialize};
+
+use super::locale::{tr, Text};
+
+/// Reputation and alignment stay within this range.
+const REPUTATION_LIMIT: i32 = 100;
+/// Alignment from which the player counts as righteous or evil.
+const ALIGNMENT_SIDE: i32 = 20;
+
+/// A sect or another group that keeps track of the player.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Faction {
+    pub id: String,
+    pub name: Text,
+    #[serde(default)]
+    pub description: Text,
+    /// Sects (门派) can be joined and teach their martial arts.
+    #[serde(default)]
+    pub sect: bool,
+}
+
+/// All factions, loaded from `assets/factions.json`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FactionLibrary {
+    factions: Vec<Faction>,
+}
+
+impl Asset for FactionLibrary {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+impl FactionLibrary {
+    pub fn load(cache: &AssetCache) -> FactionLibrary {
+        let faction_file = "factions";
+        let handle = cache.load_expect::<FactionLibrary>(faction_file);
+
+        handle.read().to_owned()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Faction> {
+        self.factions.iter().find(|f| f.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Faction> {
+        self.factions.iter()
+    }
+}
+
+/// How a faction feels about the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Standing {
+    Hostile,
+    Unfriendly,
+    Neutral,
+    Friendly,
+    Honored,
+}
+
+impl Standing {
+    pub fn of(value: i32) -> Standing {
+        match value {
+            i32::MIN..=-50 => Standing::Hostile,
+            -49..=-10 => Standing::Unfriendly,
+            -9..=9 => Standing::Neutral,
+            10..=49 => Standing::Friendly,
+            _ => Standing::Honored,
+        }
+    }
+}
+
+impl fmt::Display for Standing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            tr(match self {
+                Standing::Hostile => "reputation.hostile",
+                Standing::Unfriendly => "reputation.unfriendly",
+                Standing::Neutral => "reputation.neutral",
+                Standing::Friendly => "reputation.friendly",
+                Standing::Honored => "reputation.honored",
+            })
+        )
+    }
+}
+
+/// Where the player stands between 正 and 邪.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    Righteous,
+    Neutral,
+    Evil,
+}
+
+impl Alignment {
+    pub fn of(value: i32) -> Alignment {
+        if value >= ALIGNMENT_SIDE {
+            Alignment::Righteous
+        } else if value <= -ALIGNMENT_SIDE {
+            Alignment::Evil
+        } else {
+            Alignment::Neutral
+        }
+    }
+}
+
+impl fmt::Display for Alignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            tr(match self {
+                Alignment::Righteous => "alignment.righteous",
+                Alignment::Neutral => "alignment.neutral",
+                Alignment::Evil => "alignment.evil",
+            })
+        )
+    }
+}
+
+/// Standing of the player with factions and in the wulin.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Reputation {
+    /// Reputation with each faction. Missing factions are at 0.
+    #[serde(default)]
+    values: HashMap<String, i32>,
+    /// Positive for righteous (正), negative for evil (邪).
+    #[serde(default)]
+    alignment: i32,
+    /// Id of the sect the player belongs to.
+    #[serde(default)]
+    sect: Option<String>,
+}
+
+impl Reputation {
+    pub fn get(&self, id: &str) -> i32 {
+        self.values.get(id).copied().unwrap_or(0)
+    }
+
+    /// Change the reputation with a faction. Return the new value.
+    pub fn change(&mut self, id: &str, delta: i32) -> i32 {
+        let value = self.values.entry(id.to_string()).or_insert(0);
+        *value = (*value + delta).clamp(-REPUTATION_LIMIT, REPUTATION_LIMIT);
+        *value
+    }
+
+    pub fn alignment(&self) -> i32 {
+        self.alignment
+    }
+
+    /// Move the alignment toward 正 (positive) or 邪 (negative). Return
+    /// the new value.
+    pub fn shift_alignment(&mut self, delta: i32) -> i32 {
+        self.alignment = (self.alignment + delta).clamp(-REPUTATION_LIMIT, REPUTATION_LIMIT);
+        self.alignment
+    }
+
+    pub fn sect(&self) -> Option<&str> {
+        self.sect.as_deref()
+    }
+
+    /// Join a sect. Return false if the player already belongs to one.
+    pub fn join(&mut self, id: &str) -> bool {
+        if self.sect.is_some() {
+            return false;
+        }
+        self.sect = Some(id.to_string());
+        true
+    }
+}
+
+/// A condition on reputation that an event needs, e.g.
+/// `{ "Reputation": ["village", 10] }`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Condition {
+    /// Reputation with a faction of at least a value.
+    Reputation(String, i32),
+    /// Alignment of at least a value.
+    Righteous(i32),
+    /// Alignment of at most minus a value.
+    Evil(i32),
+    /// Member of a sect.
+    Member(String),
+}
+
+impl Condition {
+    pub fn is_met(&self, reputation: &Reputation) -> bool {
+        match self {
+            Condition::Reputation(id, value) => reputation.get(id) >= *value,
+            Condition::Righteous(value) => reputation.alignment() >= *value,
+            Condition::Evil(value) => reputation.alignment() <= -*value,
+            Condition::Member(id) => reputation.sect() == Some(id.as_str()),
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use std::{collections::HashMap, fmt};
+
+use assets_manager::{loader, Asset, AssetCache};
+use serde::{Deserialize, Deserializer};
+
+use super::PropertyType;
+
+/// An arithmetic expression over named variables, e.g. `(con + str) * 2.5`.
+///
+/// Supports numbers, variables (letters, digits, `_` and `.`), `+ - * /`,
+/// parentheses and the functions `min`, `max`, `floor`, `ceil`, `round`
+/// and `sqrt`.
+#[derive(Debug, Clone)]
+pub struct Formula {
+    source: String,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Number(f64),
+    Var(String),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = source.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut number = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+                number.push(c);
+                chars.next();
+            }
+            let value = number
+                .parse()
+                .map_err(|_| format!("invalid number `{}`", number))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut ident = String::new();
+            while let Some(&c) = chars
+                .peek()
+                .filter(|c| c.is_ascii_alphanumeric() || **c == '_' || **c == '.')
+            {
+                ident.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Ident(ident));
+        } else if "+-*/(),".contains(c) {
+            tokens.push(Token::Op(c));
+            chars.next();
+        } else {
+            return Err(format!("unexpected `{}`", c));
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser over tokens.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, op: char) -> bool {
+        if self.peek() == Some(&Token::Op(op)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: char) -> Result<(), String> {
+        if self.eat(op) {
+            Ok(())
+        } else {
+            Err(format!("expect `{}`", op))
+        }
+    }
+
+    // expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut left = self.term()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op(op)) if *op == '+' || *op == '-' => *op,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.term()?));
+        }
+    }
+
+    // term := unary (('*' | '/') unary)*
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op(op)) if *op == '*' || *op == '/' => *op,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+    }
+
+    // unary := '-' unary | atom
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat('-') {
+            Ok(Expr::Neg(Box::new(self.unary()?)))
+        } else {
+            self.atom()
+        }
+    }
+
+    // atom := number | ident | ident '(' args ')' | '(' expr ')'
+    fn atom(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(v)) => Ok(Expr::Number(v)),
+            Some(Token::Ident(name)) => {
+                if !self.eat('(') {
+                    return Ok(Expr::Var(name));
+                }
+                let mut args = vec![];
+                if !self.eat(')') {
+                    loop {
+                        args.push(self.expr()?);
+                        if self.eat(')') {
+                            break;
+                        }
+                        self.expect(',')?;
+                    }
+                }
+                check_call(&name, args.len())?;
+                Ok(Expr::Call(name, args))
+            }
+            Some(Token::Op('(')) => {
+                let expr = self.expr()?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            Some(Token::Op(op)) => Err(format!("unexpected `{}`", op)),
+            None => Err("unexpected end".to_string()),
+        }
+    }
+}
+
+fn check_call(name: &str, args: usize) -> Result<(), String> {
+    let valid = match name {
+        "min" | "max" => args >= 1,
+        "floor" | "ceil" | "round" | "sqrt" => args == 1,
+        _ => return Err(format!("unknown function `{}`", name)),
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("wrong number of arguments for `{}`", name))
+    }
+}
+
+impl Expr {
+    fn eval(&self, vars: &dyn Fn(&str) -> Option<f64>) -> Result<f64, String> {
+        Ok(match self {
+            Expr::Number(v) => *v,
+            Expr::Var(name) => vars(name).ok_or_else(|| format!("unknown variable `{}`", name))?,
+            Expr::Neg(e) => -e.eval(vars)?,
+            Expr::Binary(op, l, r) => {
+                let (l, r) = (l.eval(vars)?, r.eval(vars)?);
+                match op {
+                    '+' => l + r,
+                    '-' => l - r,
+                    '*' => l * r,
+                    _ if r == 0.0 => return Err("division by zero".to_string()),
+                    _ => l / r,
+                }
+            }
+            Expr::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|a| a.eval(vars))
+                    .collect::<Result<Vec<f64>, String>>()?;
+                match name.as_str() {
+                    "min" => args.into_iter().fold(f64::INFINITY, f64::min),
+                    "max" => args.into_iter().fold(f64::NEG_INFINITY, f64::max),
+                    "floor" => args[0].floor(),
+                    "ceil" => args[0].ceil(),
+                    "round" => args[0].round(),
+                    _ => args[0].sqrt(),
+                }
+            }
+        })
+    }
+
+    fn variables<'a>(&'a self, output: &mut Vec<&'a str>) {
+        match self {
+            Expr::Number(_) => {}
+            Expr::Var(name) => output.push(name),
+            Expr::Neg(e) => e.variables(output),
+            Expr::Binary(_, l, r) => {
+                l.variables(output);
+                r.variables(output);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|a| a.variables(output)),
+        }
+    }
+}
+
+impl Formula {
+    pub fn parse(source: &str) -> Result<Formula, String> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+        };
+        let expr = parser.expr()?;
+        if let Some(token) = parser.peek() {
+            return Err(format!("unexpected `{:?}` in `{}`", token, source));
+        }
+
+        Ok(Formula {
+            source: source.to_string(),
+            expr,
+        })
+    }
+
+    /// Evaluate the formula, looking up variables with `vars`.
+    pub fn eval(&self, vars: &dyn Fn(&str) -> Option<f64>) -> Result<f64, String> {
+        self.expr.eval(vars)
+    }
+
+    /// Names of all variables used in the formula.
+    pub fn variables(&self) -> Vec<&str> {
+        let mut output = vec![];
+        self.expr.variables(&mut output);
+        output
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Formula {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Formula::parse(&source)
+            .map_err(|e| serde::de::Error::custom(format!("{} in `{}`", e, source)))
+    }
+}
+
+/// Formulas of derived properties, loaded from `assets/formulas.json`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Formulas(HashMap<PropertyType, Formula>);
+
+impl Asset for Formulas {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+impl Formulas {
+    pub fn load(cache: &AssetCache) -> Formulas {
+        let formula_file = "formulas";
+        let handle = cache.load_expect::<Formulas>(formula_file);
+
+        handle.read().to_owned()
+    }
+
+    pub fn get(&self, prop: &PropertyType) -> Option<&Formula> {
+        self.0.get(prop)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&PropertyType, &Formula)> {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str) -> Result<f64, String> {
+        let vars = |name: &str| match name {
+            "con" => Some(10.0),
+            "attr.str" => Some(4.0),
+            _ => None,
+        };
+        Formula::parse(source)?.eval(&vars)
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(7.0));
+        assert_eq!(eval("(1 + 2) * 3"), Ok(9.0));
+        assert_eq!(eval("8 - 4 - 2"), Ok(2.0));
+        assert_eq!(eval("8 / 4 / 2"), Ok(1.0));
+        assert_eq!(eval("con * 2.5 + attr.str"), Ok(29.0));
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(eval("-3 + 5"), Ok(2.0));
+        assert_eq!(eval("--3"), Ok(3.0));
+        assert_eq!(eval("2 * -con"), Ok(-20.0));
+        assert_eq!(eval("-(1 + 2) * 2"), Ok(-6.0));
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(eval("min(con, 3, 7)"), Ok(3.0));
+        assert_eq!(eval("max(con, 3)"), Ok(10.0));
+        assert_eq!(eval("floor(2.7) + ceil(2.1) + round(2.5)"), Ok(8.0));
+        assert_eq!(eval("sqrt(16)"), Ok(4.0));
+    }
+
+    #[test]
+    fn unknown_variable() {
+        assert_eq!(eval("con + dex"), Err("unknown variable `dex`".to_string()));
+    }
+
+    #[test]
+    fn division_by_zero() {
+        assert_eq!(eval("con / (2 - 2)"), Err("division by zero".to_string()));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Formula::parse("1 +").is_err());
+        assert!(Formula::parse("(1 + 2").is_err());
+        assert!(Formula::parse("1 2").is_err());
+        assert!(Formula::parse("con $ 2").is_err());
+        assert!(Formula::parse("1..2").is_err());
+        assert!(Formula::parse("pow(2, 3)").is_err());
+        assert!(Formula::parse("sqrt(1, 2)").is_err());
+        assert!(Formula::parse("min()").is_err());
+    }
+
+    #[test]
+    fn variables() {
+        let formula = Formula::parse("max(con, attr.str) * 2 + con").unwrap();
+        assert_eq!(formula.variables(), vec!["con", "attr.str", "con"]);
+        assert_eq!(formula.to_string(), "max(con, attr.str) * 2 + con");
+    }
+}
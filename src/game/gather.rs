@@ -0,0 +1,127 @@
+use assets_manager::{loader, Asset, AssetCache};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::locale::Text;
+
+/// Items a resource node may give, e.g.
+/// `{ "item": "herb", "count": 1, "per_level": 0.5 }`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Yield {
+    pub item: String,
+    pub count: u32,
+    /// Chance between 0 and 1 to find anything.
+    #[serde(default = "default_chance")]
+    pub chance: f64,
+    /// Items added per level of the skill of the node.
+    #[serde(default)]
+    pub per_level: f64,
+}
+
+fn default_chance() -> f64 {
+    1.0
+}
+
+/// Something to gather from a tile, such as trees, herbs or animal
+/// tracks, defined in `assets/resources.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResourceNode {
+    pub id: String,
+    pub name: Text,
+    pub yields: Vec<Yield>,
+    /// Skill that raises the yields. It is practiced each time the node
+    /// is gathered.
+    #[serde(default)]
+    pub skill: Option<String>,
+    /// Minutes of game time it takes.
+    pub minutes: u32,
+    /// Jing spent.
+    pub jing: f64,
+    /// Minutes until the node can be gathered again.
+    pub respawn: u32,
+    /// Index of the tile left behind for nodes used up for good, such
+    /// as a felled tree. Those never respawn.
+    #[serde(default)]
+    pub leaves: Option<usize>,
+}
+
+impl ResourceNode {
+    /// Roll the yields for a skill level. Return item ids and counts.
+    pub fn roll(&self, level: u32) -> Vec<(String, u32)> {
+        let mut rng = rand::thread_rng();
+        self.yields
+            .iter()
+            .filter(|y| rng.gen_bool(y.chance.clamp(0.0, 1.0)))
+            .map(|y| {
+                let bonus = (y.per_level * level as f64).floor() as u32;
+                (y.item.clone(), y.count + bonus)
+            })
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+}
+
+/// All resource nodes, loaded from `assets/resources.json`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ResourceLibrary {
+    nodes: Vec<ResourceNode>,
+}
+
+impl Asset for ResourceLibrary {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+impl ResourceLibrary {
+    pub fn load(cache: &AssetCache) -> ResourceLibrary {
+        let resource_file = "resources";
+        let handle = cache.load_expect::<ResourceLibrary>(resource_file);
+
+        handle.read().to_owned()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ResourceNode> {
+        self.nodes.iter().find(|n| n.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ResourceNode> {
+        self.nodes.iter()
+    }
+}
+
+/// A gathered node waiting to respawn.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Depleted {
+    map: String,
+    pos: (usize, usize),
+    /// Game minute when it can be gathered again.
+    ready: u64,
+}
+
+/// Resource nodes that were gathered and have not respawned yet.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Harvests(Vec<Depleted>);
+
+impl Harvests {
+    pub fn is_depleted(&self, map: &str, pos: (usize, usize), now: u64) -> bool {
+        self.0
+            .iter()
+            .any(|d| d.map == map && d.pos == pos && d.ready > now)
+    }
+
+    /// Mark a node as gathered until a game minute.
+    pub fn deplete(&mut self, map: &str, pos: (usize, usize), ready: u64) {
+        self.0.retain(|d| d.map != map || d.pos != pos);
+        self.0.push(Depleted {
+            map: map.to_string(),
+            pos,
+            ready,
+        });
+    }
+
+    /// Forget nodes that have respawned.
+    pub fn respawn(&mut self, now: u64) {
+        self.0.retain(|d| d.ready > now);
+    }
+}
@@ -0,0 +1,238 @@
+use std::{collections::HashMap, fmt};
+
+use assets_manager::{loader, Asset, AssetCache};
+use serde::{Deserialize, Serialize};
+
+use super::{locale::tr, Activity, ModOp, Modifier};
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, Hash, PartialEq, Eq)]
+pub enum InjuryKind {
+    /// Cuts and bruises (外伤).
+    #[default]
+    External,
+    /// Hurt meridians and organs (内伤).
+    Internal,
+}
+
+impl fmt::Display for InjuryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            tr(match self {
+                InjuryKind::External => "injury.external",
+                InjuryKind::Internal => "injury.internal",
+            })
+        )
+    }
+}
+
+/// How an injury hurts and heals, defined in `assets/injuries.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InjuryRule {
+    pub kind: InjuryKind,
+    pub max_severity: u32,
+    /// Modifiers applied once per severity.
+    pub modifiers: Vec<Modifier>,
+    /// Minutes of activity until an untreated injury gets one severity
+    /// worse.
+    pub worsen: u32,
+    /// Activities that treat the injury, with the minutes they take to
+    /// heal one severity.
+    #[serde(default)]
+    pub heal: HashMap<Activity, u32>,
+}
+
+/// How an injury changed over time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InjuryChange {
+    Worse,
+    Better,
+    Healed,
+}
+
+/// An injury the player suffers from. Its rule stays in the
+/// [`InjuryLibrary`], so changes to it reach suffered injuries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Injury {
+    pub kind: InjuryKind,
+    pub severity: u32,
+    /// Minutes toward the next change of severity.
+    #[serde(default)]
+    progress: u32,
+}
+
+impl Injury {
+    /// Modifiers with the severity applied, to show what the injury does.
+    pub fn effects(&self, rule: &InjuryRule) -> Vec<Modifier> {
+        rule.modifiers
+            .iter()
+            .map(|m| Modifier {
+                target: m.target,
+                op: match m.op {
+                    ModOp::Add(x) => ModOp::Add(x * self.severity as f64),
+                    ModOp::Mul(x) => ModOp::Mul(x.powi(self.severity as i32)),
+                },
+            })
+            .collect()
+    }
+
+    /// Let some minutes of an activity pass.
+    fn pass_minutes(
+        &mut self,
+        rule: &InjuryRule,
+        minutes: u32,
+        activity: Activity,
+    ) -> Option<InjuryChange> {
+        let (step, healing) = match rule.heal.get(&activity) {
+            Some(step) => (*step, true),
+            None if activity == Activity::Active => (rule.worsen, false),
+            None => return None,
+        };
+        if step == 0 {
+            return None;
+        }
+
+        self.progress += minutes;
+        let steps = self.progress / step;
+        self.progress %= step;
+        if steps == 0 {
+            return None;
+        }
+
+        if healing {
+            self.severity = self.severity.saturating_sub(steps);
+            Some(if self.severity == 0 {
+                InjuryChange::Healed
+            } else {
+                InjuryChange::Better
+            })
+        } else {
+            let worse = (self.severity + steps).min(rule.max_severity);
+            (worse > self.severity).then(|| {
+                self.severity = worse;
+                InjuryChange::Worse
+            })
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Injuries(Vec<Injury>);
+
+impl Injuries {
+    /// Add severity to an injury, or suffer a new one. Return the new
+    /// severity.
+    pub fn inflict(&mut self, rule: &InjuryRule, severity: u32) -> u32 {
+        let max = rule.max_severity.max(1);
+        match self.0.iter_mut().find(|i| i.kind == rule.kind) {
+            Some(injury) => {
+                injury.severity = (injury.severity + severity).min(max);
+                injury.severity
+            }
+            None => {
+                let severity = severity.min(max);
+                self.0.push(Injury {
+                    kind: rule.kind,
+                    severity,
+                    progress: 0,
+                });
+                severity
+            }
+        }
+    }
+
+    /// Lower the severity of an injury. Return the severity left, or None
+    /// if there was no such injury.
+    pub fn treat(&mut self, kind: InjuryKind, amount: u32) -> Option<u32> {
+        let injury = self.0.iter_mut().find(|i| i.kind == kind)?;
+        injury.severity = injury.severity.saturating_sub(amount);
+        let left = injury.severity;
+        self.0.retain(|i| i.severity > 0);
+        Some(left)
+    }
+
+    /// Let some minutes of an activity pass. Injuries worsen while active
+    /// and heal with the activities that treat them. Injuries no longer
+    /// defined stay as they are.
+    pub fn pass_minutes(
+        &mut self,
+        minutes: u32,
+        activity: Activity,
+        library: &InjuryLibrary,
+    ) -> Vec<(InjuryKind, InjuryChange)> {
+        let changes = self
+            .0
+            .iter_mut()
+            .filter_map(|i| {
+                let rule = library.get(i.kind)?;
+                i.pass_minutes(rule, minutes, activity)
+                    .map(|change| (i.kind, change))
+            })
+            .collect();
+        self.0.retain(|i| i.severity > 0);
+        changes
+    }
+
+    /// Severity of an injury, 0 if the player does not suffer from it.
+    pub fn severity(&self, kind: InjuryKind) -> u32 {
+        self.0
+            .iter()
+            .find(|i| i.kind == kind)
+            .map_or(0, |i| i.severity)
+    }
+
+    /// Severity of the worst injury, 0 without injuries.
+    pub fn worst(&self) -> u32 {
+        self.0.iter().map(|i| i.severity).max().unwrap_or(0)
+    }
+
+    /// All modifiers of injuries with their severity. Injuries no longer
+    /// defined are left out.
+    pub fn modifiers<'a>(
+        &'a self,
+        library: &'a InjuryLibrary,
+    ) -> impl Iterator<Item = (&'a Modifier, u32)> + Clone {
+        self.0
+            .iter()
+            .filter_map(|i| library.get(i.kind).map(|rule| (i, rule)))
+            .flat_map(|(i, rule)| rule.modifiers.iter().map(move |m| (m, i.severity)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Injury> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// All injury rules.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct InjuryLibrary {
+    injuries: Vec<InjuryRule>,
+}
+
+impl Asset for InjuryLibrary {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+impl InjuryLibrary {
+    pub fn load(cache: &AssetCache) -> InjuryLibrary {
+        let injury_file = "injuries";
+        let handle = cache.load_expect::<InjuryLibrary>(injury_file);
+
+        handle.read().to_owned()
+    }
+
+    pub fn get(&self, kind: InjuryKind) -> Option<&InjuryRule> {
+        self.injuries.iter().find(|i| i.kind == kind)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &InjuryRule> {
+        self.injuries.iter()
+    }
+}
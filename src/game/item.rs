@@ -0,0 +1,298 @@
+use std::{collections::HashMap, fmt};
+
+use assets_manager::{loader, Asset, AssetCache};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    locale::{tr, Text},
+    InjuryKind, Modifier, PropertyType, WeaponKind,
+};
+
+/// Most stacks a character can carry.
+pub const INVENTORY_SLOTS: usize = 24;
+/// Weight anyone can carry, before strength.
+const CARRY_BASE: f64 = 20.0;
+/// Weight added per point of strength.
+const CARRY_PER_STR: f64 = 3.0;
+
+/// Weight a character with some strength can carry.
+pub fn carry_limit(str: usize) -> f64 {
+    CARRY_BASE + CARRY_PER_STR * str as f64
+}
+
+/// An item, defined in `assets/items.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Item {
+    pub id: String,
+    pub name: Text,
+    #[serde(default)]
+    pub description: Text,
+    /// Weight of one item.
+    #[serde(default)]
+    pub weight: f64,
+    /// Most items in one inventory slot.
+    #[serde(default = "default_stack")]
+    pub stack: u32,
+    pub category: ItemCategory,
+    /// What happens when the item is used. Items without an effect
+    /// cannot be used.
+    #[serde(default)]
+    pub effect: Option<ItemEffect>,
+    /// Modifiers applied while the item is equipped.
+    #[serde(default)]
+    pub modifiers: Vec<Modifier>,
+    /// Kind of a weapon.
+    #[serde(default)]
+    pub weapon: Option<WeaponKind>,
+    /// Base price in 文. Items without a price cannot be traded.
+    #[serde(default)]
+    pub price: u32,
+}
+
+fn default_stack() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ItemCategory {
+    Food,
+    Medicine,
+    Material,
+    Weapon,
+    Armor,
+    Accessory,
+    Currency,
+    Misc,
+}
+
+impl fmt::Display for ItemCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            tr(match self {
+                ItemCategory::Food => "item.category.food",
+                ItemCategory::Medicine => "item.category.medicine",
+                ItemCategory::Material => "item.category.material",
+                ItemCategory::Weapon => "item.category.weapon",
+                ItemCategory::Armor => "item.category.armor",
+                ItemCategory::Accessory => "item.category.accessory",
+                ItemCategory::Currency => "item.category.currency",
+                ItemCategory::Misc => "item.category.misc",
+            })
+        )
+    }
+}
+
+/// Effect of using an item, e.g.
+/// `{ "restore": { "Jing": 20 }, "buffs": ["well_fed"] }`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ItemEffect {
+    /// Current properties restored.
+    #[serde(default)]
+    pub restore: HashMap<PropertyType, f64>,
+    /// Buff ids applied.
+    #[serde(default)]
+    pub buffs: Vec<String>,
+    /// Severity of injuries treated.
+    #[serde(default)]
+    pub cure: HashMap<InjuryKind, u32>,
+}
+
+/// Items of the same kind in one inventory slot. The definition stays
+/// in the [`ItemLibrary`], so changes to it reach carried items.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemStack {
+    /// Item id.
+    pub item: String,
+    pub count: u32,
+}
+
+/// Items carried by a character.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Inventory(Vec<ItemStack>);
+
+impl Inventory {
+    /// Add items, filling existing stacks first. Stop when the slots run
+    /// out or the items outweigh the room left. Return the number of
+    /// items added.
+    pub fn add(&mut self, item: &Item, count: u32, room: f64) -> u32 {
+        let stack = item.stack.max(1);
+        let fits = if item.weight > 0.0 {
+            (room / item.weight).floor().max(0.0) as u32
+        } else {
+            u32::MAX
+        };
+        let mut left = count.min(fits);
+        let added = left;
+
+        for s in self.0.iter_mut().filter(|s| s.item == item.id) {
+            let put = left.min(stack.saturating_sub(s.count));
+            s.count += put;
+            left -= put;
+        }
+        while left > 0 && self.0.len() < INVENTORY_SLOTS {
+            let put = left.min(stack);
+            self.0.push(ItemStack {
+                item: item.id.clone(),
+                count: put,
+            });
+            left -= put;
+        }
+
+        added - left
+    }
+
+    /// Remove items, starting with the last stack. Return the number of
+    /// items removed.
+    pub fn remove(&mut self, id: &str, count: u32) -> u32 {
+        let mut left = count;
+        for s in self.0.iter_mut().rev().filter(|s| s.item == id) {
+            let take = left.min(s.count);
+            s.count -= take;
+            left -= take;
+        }
+        self.0.retain(|s| s.count > 0);
+
+        count - left
+    }
+
+    /// Remove a whole slot.
+    pub fn remove_slot(&mut self, index: usize) -> Option<ItemStack> {
+        (index < self.0.len()).then(|| self.0.remove(index))
+    }
+
+    /// Number of items with an id in all stacks.
+    pub fn count(&self, id: &str) -> u32 {
+        self.0
+            .iter()
+            .filter(|s| s.item == id)
+            .map(|s| s.count)
+            .sum()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&ItemStack> {
+        self.0.get(index)
+    }
+
+    /// Weight of all items. Items no longer defined weigh nothing.
+    pub fn weight(&self, library: &ItemLibrary) -> f64 {
+        self.0
+            .iter()
+            .filter_map(|s| library.get(&s.item).map(|i| i.weight * s.count as f64))
+            .sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ItemStack> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// All item definitions.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ItemLibrary {
+    items: Vec<Item>,
+}
+
+impl Asset for ItemLibrary {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+impl ItemLibrary {
+    pub fn load(cache: &AssetCache) -> ItemLibrary {
+        let item_file = "items";
+        let handle = cache.load_expect::<ItemLibrary>(item_file);
+
+        handle.read().to_owned()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Item> {
+        self.items.iter().find(|i| i.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Item> {
+        self.items.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str, weight: f64, stack: u32) -> Item {
+        Item {
+            id: id.to_string(),
+            name: Text::default(),
+            description: Text::default(),
+            weight,
+            stack,
+            category: ItemCategory::Misc,
+            effect: None,
+            modifiers: vec![],
+            weapon: None,
+            price: 0,
+        }
+    }
+
+    fn counts(inventory: &Inventory) -> Vec<(&str, u32)> {
+        inventory
+            .iter()
+            .map(|s| (s.item.as_str(), s.count))
+            .collect()
+    }
+
+    #[test]
+    fn fill_partial_stacks_first() {
+        let (herb, wood) = (item("herb", 0.0, 5), item("wood", 0.0, 5));
+        let mut inventory = Inventory::default();
+        assert_eq!(inventory.add(&herb, 3, 0.0), 3);
+        assert_eq!(inventory.add(&wood, 1, 0.0), 1);
+        assert_eq!(inventory.add(&herb, 8, 0.0), 8);
+        assert_eq!(
+            counts(&inventory),
+            vec![("herb", 5), ("wood", 1), ("herb", 5), ("herb", 1)]
+        );
+        assert_eq!(inventory.count("herb"), 11);
+    }
+
+    #[test]
+    fn stop_at_slot_limit() {
+        let herb = item("herb", 0.0, 2);
+        let mut inventory = Inventory::default();
+        let most = INVENTORY_SLOTS as u32 * 2;
+        assert_eq!(inventory.add(&herb, most + 3, 0.0), most);
+        assert_eq!(inventory.len(), INVENTORY_SLOTS);
+        assert_eq!(inventory.add(&item("wood", 0.0, 5), 1, 0.0), 0);
+    }
+
+    #[test]
+    fn stop_at_weight_limit() {
+        let meat = item("meat", 3.0, 10);
+        let mut inventory = Inventory::default();
+        assert_eq!(inventory.add(&meat, 5, 10.0), 3);
+        assert_eq!(inventory.add(&meat, 1, 2.9), 0);
+        assert_eq!(inventory.add(&meat, 1, -1.0), 0);
+        assert_eq!(counts(&inventory), vec![("meat", 3)]);
+    }
+
+    #[test]
+    fn remove_from_last_stack() {
+        let herb = item("herb", 0.0, 5);
+        let mut inventory = Inventory::default();
+        inventory.add(&herb, 7, 0.0);
+        assert_eq!(inventory.remove("herb", 3), 3);
+        assert_eq!(counts(&inventory), vec![("herb", 4)]);
+        assert_eq!(inventory.remove("herb", 9), 4);
+        assert!(inventory.is_empty());
+    }
+}
@@ -0,0 +1,42 @@
+use assets_manager::{loader, Asset, AssetCache};
+use serde::Deserialize;
+
+/// Experience needed for each level, loaded from `assets/levels.json`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Levels {
+    /// Total experience to reach level 2, 3 and so on. The last entry is
+    /// the top level.
+    experience: Vec<u32>,
+    /// Attribute points gained with each level.
+    points: u32,
+}
+
+impl Asset for Levels {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+impl Levels {
+    pub fn load(cache: &AssetCache) -> Levels {
+        let level_file = "levels";
+        let handle = cache.load_expect::<Levels>(level_file);
+
+        handle.read().to_owned()
+    }
+
+    /// Total experience to reach the level after a level, None at the
+    /// top level.
+    pub fn next(&self, level: u32) -> Option<u32> {
+        let index = (level as usize).checked_sub(1)?;
+        self.experience.get(index).copied()
+    }
+
+    pub fn points(&self) -> u32 {
+        self.points
+    }
+
+    pub fn experience(&self) -> &[u32] {
+        &self.experience
+    }
+}
@@ -0,0 +1,164 @@
+use std::{collections::BTreeMap, fmt, sync::RwLock};
+
+use assets_manager::{loader, Asset, AssetCache};
+use serde::{Deserialize, Serialize};
+
+/// Language used when a string is missing in the selected language.
+pub const FALLBACK_LANGUAGE: &str = "zh";
+
+/// Strings of one language, loaded from `assets/locales/<language>.json`.
+#[derive(Default, Deserialize, Clone)]
+pub struct Locale {
+    /// Name of the language shown in settings.
+    pub name: String,
+    strings: BTreeMap<String, String>,
+}
+
+impl Asset for Locale {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+impl Locale {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.strings.get(key).map(|s| s.as_str())
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.strings.keys().map(|k| k.as_str())
+    }
+}
+
+/// Text of game content, either one string or one string per language,
+/// e.g. `"野猪"` or `{ "zh": "野猪", "en": "Wild boar" }`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Text {
+    Plain(String),
+    Localized(BTreeMap<String, String>),
+}
+
+impl Text {
+    /// The text in the selected language. Fall back to
+    /// [`FALLBACK_LANGUAGE`] and then to any language it is written in.
+    pub fn get(&self) -> &str {
+        match self {
+            Text::Plain(text) => text,
+            Text::Localized(texts) => texts
+                .get(&language())
+                .or_else(|| texts.get(FALLBACK_LANGUAGE))
+                .or_else(|| texts.values().next())
+                .map_or("", |t| t.as_str()),
+        }
+    }
+
+    /// Languages the text is written in, None if it is the same in all.
+    pub fn languages(&self) -> Option<impl Iterator<Item = &str>> {
+        match self {
+            Text::Plain(_) => None,
+            Text::Localized(texts) => Some(texts.keys().map(|l| l.as_str())),
+        }
+    }
+}
+
+impl Default for Text {
+    fn default() -> Self {
+        Text::Plain(String::new())
+    }
+}
+
+impl fmt::Display for Text {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.get())
+    }
+}
+
+struct Locales {
+    language: String,
+    all: BTreeMap<String, Locale>,
+}
+
+// Display impls have no access to the game state, so the loaded
+// locales live here.
+static LOCALES: RwLock<Option<Locales>> = RwLock::new(None);
+
+/// Load all locales from assets and select a language.
+pub fn init(cache: &AssetCache, language: &str) {
+    let all = load_all(cache)
+        .into_iter()
+        .filter_map(|(id, locale)| locale.ok().map(|l| (id, l)))
+        .collect();
+
+    *LOCALES.write().unwrap() = Some(Locales {
+        language: language.to_string(),
+        all,
+    });
+}
+
+/// Load every locale in `assets/locales`, keyed by language id.
+pub fn load_all(cache: &AssetCache) -> Vec<(String, Result<Locale, String>)> {
+    match cache.load_dir::<Locale>("locales", false) {
+        Ok(dir) => dir
+            .ids()
+            .map(|id| {
+                let language = id.trim_start_matches("locales.").to_string();
+                let locale = cache
+                    .load::<Locale>(id)
+                    .map(|h| h.read().to_owned())
+                    .map_err(|e| e.to_string());
+                (language, locale)
+            })
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+pub fn set_language(language: &str) {
+    if let Some(locales) = LOCALES.write().unwrap().as_mut() {
+        locales.language = language.to_string();
+    }
+}
+
+/// Id of the selected language.
+pub fn language() -> String {
+    match LOCALES.read().unwrap().as_ref() {
+        Some(locales) => locales.language.clone(),
+        None => FALLBACK_LANGUAGE.to_string(),
+    }
+}
+
+/// Ids and names of all loaded languages.
+pub fn languages() -> Vec<(String, String)> {
+    match LOCALES.read().unwrap().as_ref() {
+        Some(locales) => locales
+            .all
+            .iter()
+            .map(|(id, locale)| (id.clone(), locale.name.clone()))
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// Translate a key into the selected language.
+///
+/// Fall back to [`FALLBACK_LANGUAGE`] and then to the key itself if the
+/// string is missing.
+pub fn tr(key: &str) -> String {
+    if let Some(locales) = LOCALES.read().unwrap().as_ref() {
+        for language in [locales.language.as_str(), FALLBACK_LANGUAGE] {
+            if let Some(text) = locales.all.get(language).and_then(|l| l.get(key)) {
+                return text.to_string();
+            }
+        }
+    }
+
+    key.to_string()
+}
+
+/// Translate a key and replace `{0}`, `{1}`, ... with the arguments.
+pub fn trf(key: &str, args: &[&str]) -> String {
+    args.iter().enumerate().fold(tr(key), |text, (i, arg)| {
+        text.replace(&format!("{{{}}}", i), arg)
+    })
+}
@@ -0,0 +1,142 @@
+use crate::components::message::{MsgStyle, StyledText};
+
+/// Parse inline markup into styled text pieces.
+///
+/// Styled text is wrapped in tags, e.g. `往[target]村子东南[/target]走`.
+/// Tags can be nested, the innermost style wins. Use `[[` to write a
+/// literal `[`. Tag names other than the built-in styles are kept as
+/// [`MsgStyle::Custom`] and looked up in the theme when drawn.
+pub fn parse(text: &str) -> Result<StyledText, String> {
+    let mut output: StyledText = vec![];
+    let mut stack: Vec<&str> = vec![];
+    let mut buffer = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('[') {
+        let (head, tail) = rest.split_at(start);
+        buffer.push_str(head);
+
+        if let Some(after) = tail.strip_prefix("[[") {
+            buffer.push('[');
+            rest = after;
+            continue;
+        }
+
+        let end = tail
+            .find(']')
+            .ok_or_else(|| format!("unclosed tag in `{}`", text))?;
+        let tag = &tail[1..end];
+        rest = &tail[end + 1..];
+
+        // flush text written in the current style
+        push(&mut output, &mut buffer, stack.last().copied());
+
+        match tag.strip_prefix('/') {
+            Some(name) => match stack.pop() {
+                Some(open) if open == name => {}
+                Some(open) => {
+                    return Err(format!(
+                        "expect `[/{}]` but found `[/{}]` in `{}`",
+                        open, name, text
+                    ))
+                }
+                None => return Err(format!("unexpected `[/{}]` in `{}`", name, text)),
+            },
+            None => {
+                if !is_tag_name(tag) {
+                    return Err(format!("invalid tag `[{}]` in `{}`", tag, text));
+                }
+                stack.push(tag);
+            }
+        }
+    }
+
+    buffer.push_str(rest);
+    if let Some(open) = stack.last() {
+        return Err(format!("missing `[/{}]` in `{}`", open, text));
+    }
+    push(&mut output, &mut buffer, None);
+
+    Ok(output)
+}
+
+fn push(output: &mut StyledText, buffer: &mut String, tag: Option<&str>) {
+    if !buffer.is_empty() {
+        let style = tag.map_or(MsgStyle::Default, MsgStyle::from_name);
+        output.push((std::mem::take(buffer), style));
+    }
+}
+
+fn is_tag_name(tag: &str) -> bool {
+    !tag.is_empty()
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn piece(text: &str, style: MsgStyle) -> (String, MsgStyle) {
+        (text.to_string(), style)
+    }
+
+    #[test]
+    fn plain_text() {
+        assert_eq!(
+            parse("回到了村子"),
+            Ok(vec![piece("回到了村子", MsgStyle::Default)])
+        );
+        assert_eq!(parse(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn tags() {
+        assert_eq!(
+            parse("往[target]村子东南[/target]走"),
+            Ok(vec![
+                piece("往", MsgStyle::Default),
+                piece("村子东南", MsgStyle::Target),
+                piece("走", MsgStyle::Default),
+            ])
+        );
+        assert_eq!(
+            parse("[place]浣花村[/place]"),
+            Ok(vec![piece("浣花村", MsgStyle::Custom("place".to_string()))])
+        );
+    }
+
+    #[test]
+    fn nested_tags() {
+        assert_eq!(
+            parse("[hint]按[danger]q[/danger]退出[/hint]"),
+            Ok(vec![
+                piece("按", MsgStyle::Hint),
+                piece("q", MsgStyle::Danger),
+                piece("退出", MsgStyle::Hint),
+            ])
+        );
+    }
+
+    #[test]
+    fn escaped_bracket() {
+        assert_eq!(
+            parse("[[1] [item]兔子[/item]"),
+            Ok(vec![
+                piece("[1] ", MsgStyle::Default),
+                piece("兔子", MsgStyle::Item),
+            ])
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse("[hint]no end").is_err());
+        assert!(parse("[hint]wrong[/item]").is_err());
+        assert!(parse("stray[/hint]").is_err());
+        assert!(parse("unclosed [hint").is_err());
+        assert!(parse("[bad tag]x[/bad tag]").is_err());
+        assert!(parse("[]x").is_err());
+    }
+}
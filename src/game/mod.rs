@@ -1,12 +1,37 @@
+mod background;
+mod buff;
 mod character;
+pub mod check;
+mod combat;
+mod defeat;
+mod enemy;
+mod equipment;
 mod events;
+mod faction;
+mod formula;
+mod gather;
+mod injury;
+mod item;
+mod level;
+pub mod locale;
+pub mod markup;
+mod recipe;
+mod regen;
+mod rules;
+pub mod settings;
+mod shop;
+mod skill;
 mod state;
+pub mod template;
+mod terrain;
 mod ui;
 
 use std::{
     collections::HashMap,
+    fmt,
     fs::{self, File},
     io::prelude::*,
+    path::{Path, PathBuf},
 };
 
 use assets_manager::{loader, Asset, AssetCache};
@@ -14,12 +39,31 @@ use chrono::{DateTime, Utc};
 use crossterm::event::{KeyCode, KeyEvent};
 use serde::{Deserialize, Serialize};
 
+pub use background::*;
+pub use buff::*;
 pub use character::*;
+pub use combat::*;
+pub use defeat::*;
+pub use enemy::*;
+pub use equipment::*;
 pub use events::*;
+pub use faction::*;
+pub use formula::*;
+pub use gather::*;
+pub use injury::*;
+pub use item::*;
+pub use level::*;
+pub use recipe::*;
+pub use regen::*;
+pub use rules::*;
+pub use shop::*;
+pub use skill::*;
 pub use state::GameState;
+pub use terrain::*;
 pub use ui::GameUI;
 
-use crate::components::{map::Maps, Id};
+use crate::components::{creation::Creation, map::Maps, player::Player, Id};
+use locale::tr;
 
 pub struct Game {
     pub ui: GameUI,
@@ -29,47 +73,125 @@ pub struct Game {
 
 impl Game {
     pub fn on_key(&mut self, key: KeyEvent) {
-        match &self.state.game_mode {
+        // any key leaves a finished game once its story is read
+        if self.state.game_over && !self.state.messages.is_pending() {
+            self.end_game();
+            return;
+        }
+
+        let message = match &self.state.game_mode {
             Some(mode) => match mode {
-                GameMode::Edit => {}
+                GameMode::Edit => None,
                 GameMode::Story => match key.code {
                     KeyCode::Char('q') => {
                         self.state.should_quit = true;
                         self.save_game();
+                        None
                     }
                     _ => self.ui.on_key(key, &mut self.state),
                 },
             },
             None => self.ui.on_key(key, &mut self.state),
+        };
+
+        if let Some(message) = message {
+            self.on_message(message);
         }
+        self.ui.follow_battle(&self.state);
+        self.ui.follow_shop(&self.state);
     }
 
-    pub fn load_save(&mut self) {
-        // TODO: Add load from save
-        self.state.game_mode = Some(GameMode::Story);
+    fn on_message(&mut self, message: Message) {
+        match message {
+            Message::OpenCreation => {
+                self.ui.creation = Some(Creation::new(&self.cache));
+                self.ui.focus(Id::Creation);
+            }
+            Message::CancelCreation => {
+                self.ui.creation = None;
+                self.ui.focus(Id::Dashboard);
+            }
+            Message::StartGame => {
+                if let Some(creation) = self.ui.creation.take() {
+                    let (player, commands) = creation.finish();
+                    self.start_game(player, commands);
+                }
+            }
+            Message::LoadSave(path) => self.load_save(&path),
+        }
+    }
+
+    /// Continue a saved game. The settings are kept, and later saves
+    /// of the game overwrite the loaded file.
+    pub fn load_save(&mut self, path: &Path) {
+        let state = fs::read_to_string(path)
+            .ok()
+            .and_then(|json| GameState::from_json(&self.cache, &json).ok());
+        let mut state = match state {
+            Some(state) => state,
+            None => {
+                self.ui.save_menu.report(tr("saves.broken"));
+                return;
+            }
+        };
+
+        state.settings = std::mem::take(&mut self.state.settings);
+        state.save_file = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned());
+        state.game_mode = Some(GameMode::Story);
+        self.state = state;
+        self.ui.focus(Id::Map);
     }
 
-    pub fn start_game(&mut self) {
+    /// Start a new game with a created character. Commands of the
+    /// chosen background run after the world is loaded.
+    pub fn start_game(&mut self, player: Player, commands: Vec<EventCommand>) {
         self.state.game_mode = Some(GameMode::Story);
         self.state.curr_map = Some(Maps::HuanHuaCun("tiles".to_string()));
+        self.state.player = player;
         self.state.load(&self.cache);
+        for command in commands {
+            self.state.execute(command);
+        }
         self.ui.focus(Id::Map);
     }
 
-    pub fn save_game(&self) {
-        if let Some(config_path) = dirs::data_dir() {
-            let config_path = config_path.join("wuxia");
-            let save_dir = config_path.join("saves");
+    /// Leave a game that ended with the death of the player. With
+    /// permadeath its save is deleted, so it cannot be continued.
+    fn end_game(&mut self) {
+        if self.state.settings.permadeath {
+            self.delete_save();
+        }
+
+        let settings = self.state.settings.clone();
+        self.state = GameState::new();
+        self.state.settings = settings;
+        self.ui.focus(Id::Dashboard);
+    }
+
+    /// Save the game. The file is named on the first save and kept in
+    /// the game state, later saves overwrite it.
+    pub fn save_game(&mut self) {
+        if let Some(save_dir) = save_dir() {
             if !save_dir.is_dir() {
                 // create save directory
                 fs::create_dir_all(&save_dir).unwrap();
             }
 
+            let name = self
+                .state
+                .save_file
+                .get_or_insert_with(|| {
+                    let now: DateTime<Utc> = Utc::now();
+                    format!("autosave-{}", now.to_rfc3339())
+                })
+                .clone();
+
             // parse game state to string    }
             match serde_json::to_string(&self.state) {
                 Ok(game_state) => {
-                    let now: DateTime<Utc> = Utc::now();
-                    let save_file = save_dir.join(format!("autosave-{}", now.to_rfc3339()));
+                    let save_file = save_dir.join(name);
                     let mut file = File::create(&save_file).expect("failed to create save file");
                     file.write_all(game_state.as_bytes())
                         .expect("failed to write to save file");
@@ -79,14 +201,27 @@ impl Game {
         }
     }
 
+    /// Delete the save of this game. Saves of other games are kept.
+    fn delete_save(&self) {
+        if let (Some(dir), Some(name)) = (save_dir(), &self.state.save_file) {
+            // a save that cannot be deleted is left behind
+            let _ = fs::remove_file(dir.join(name));
+        }
+    }
+
     pub fn on_tick(&mut self) {
         let cache = &self.cache;
 
         // call on_tick() on UI and state
         self.state.on_tick(cache);
+        self.ui.follow_battle(&self.state);
     }
 }
 
+fn save_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|p| p.join("wuxia").join("saves"))
+}
+
 #[derive(Serialize, Deserialize)]
 pub enum GameMode {
     Story,
@@ -121,11 +256,20 @@ impl GameSwitch {
         }
     }
 
+    pub fn set(&mut self, name: &str, state: bool) {
+        self.0.insert(name.to_string(), state);
+    }
+
+    /// Check if a switch is declared.
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
     /// Check if a list of switches is on.
     ///
     /// Empty list will always return true. For non empty list,
     /// all switches must be on for the return to be true.
-    pub fn is_all_on(&self, others: &Vec<String>) -> bool {
+    pub fn is_all_on(&self, others: &[String]) -> bool {
         let mut active = true;
         let mut index = 0;
 
@@ -138,8 +282,60 @@ impl GameSwitch {
     }
 }
 
+#[derive(Default, Serialize, Deserialize, Clone)]
+pub struct GameVariable(HashMap<String, Value>);
+
+impl Asset for GameVariable {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+
+    const HOT_RELOADED: bool = true;
+}
+
+impl GameVariable {
+    pub fn load(cache: &AssetCache) -> GameVariable {
+        let variable_file = "variables";
+        let handle = cache.load_expect::<GameVariable>(variable_file);
+
+        handle.read().to_owned()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.0.get(name)
+    }
+
+    pub fn set(&mut self, name: &str, value: Value) {
+        self.0.insert(name.to_string(), value);
+    }
+
+    /// Check if a variable is declared.
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
 pub enum Value {
     Int(usize),
 }
 
-pub enum Message {}
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// Requests from the UI that need more than the game state.
+pub enum Message {
+    /// Open character creation.
+    OpenCreation,
+    CancelCreation,
+    /// Start a game with the created character.
+    StartGame,
+    /// Continue the game saved in a file.
+    LoadSave(PathBuf),
+}
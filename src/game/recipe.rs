@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use assets_manager::{loader, Asset, AssetCache};
+use serde::Deserialize;
+
+use super::locale::Text;
+
+/// Some items with an id, e.g. `{ "item": "rabbit", "count": 1 }`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Ingredient {
+    pub item: String,
+    pub count: u32,
+}
+
+/// A way to cook, brew or make items, defined in `assets/recipes.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Recipe {
+    pub id: String,
+    pub name: Text,
+    #[serde(default)]
+    pub description: Text,
+    /// Items used up.
+    pub inputs: Vec<Ingredient>,
+    /// Item ids that must be carried, but are not used up.
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// Station of a tile the player must stand on or next to.
+    #[serde(default)]
+    pub station: Option<String>,
+    /// Minutes of game time it takes.
+    pub minutes: u32,
+    pub outputs: Vec<Ingredient>,
+    /// Least level of skills, by skill id. The skills are practiced
+    /// each time the recipe is made.
+    #[serde(default)]
+    pub skills: HashMap<String, u32>,
+}
+
+/// All recipes, loaded from `assets/recipes.json`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RecipeLibrary {
+    recipes: Vec<Recipe>,
+}
+
+impl Asset for RecipeLibrary {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+impl RecipeLibrary {
+    pub fn load(cache: &AssetCache) -> RecipeLibrary {
+        let recipe_file = "recipes";
+        let handle = cache.load_expect::<RecipeLibrary>(recipe_file);
+
+        handle.read().to_owned()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Recipe> {
+        self.recipes.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Recipe> {
+        self.recipes.iter()
+    }
+}
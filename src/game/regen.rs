@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use assets_manager::{loader, Asset, AssetCache};
+use serde::{Deserialize, Serialize};
+
+use super::{Formula, PropertyType};
+
+/// What the player does while time passes.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Hash, PartialEq, Eq)]
+pub enum Activity {
+    /// Walking around, practicing and everything else.
+    Active,
+    Resting,
+    /// Sleeping at home.
+    Sleeping,
+    /// Meditating with an inner art to cultivate inner energy.
+    Meditating,
+}
+
+/// Regeneration of current properties per hour (时辰) of game time,
+/// loaded from `assets/regen.json`, e.g.
+/// `{ "Resting": { "Jing": "con * 2" } }`.
+///
+/// Formulas take the same variables as `assets/formulas.json`, plus
+/// `night` which is 1 at night and 0 during the day. A formula for
+/// MaxNeili cultivates inner energy.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Regen(HashMap<Activity, HashMap<PropertyType, Formula>>);
+
+impl Asset for Regen {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+impl Regen {
+    pub fn load(cache: &AssetCache) -> Regen {
+        let regen_file = "regen";
+        let handle = cache.load_expect::<Regen>(regen_file);
+
+        handle.read().to_owned()
+    }
+
+    pub fn get(&self, activity: Activity, prop: &PropertyType) -> Option<&Formula> {
+        self.0.get(&activity).and_then(|f| f.get(prop))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Activity, &PropertyType, &Formula)> {
+        self.0
+            .iter()
+            .flat_map(|(a, f)| f.iter().map(move |(p, f)| (a, p, f)))
+    }
+}
@@ -0,0 +1,27 @@
+use assets_manager::AssetCache;
+
+use super::{BuffLibrary, Formulas, InjuryLibrary, ItemLibrary, SkillLibrary};
+
+/// Definitions looked up while playing. Characters keep only the ids of
+/// what they learned, carry, suffer or are affected by, so reloaded
+/// definitions reach them at once.
+#[derive(Default)]
+pub struct Rules {
+    pub buffs: BuffLibrary,
+    pub formulas: Formulas,
+    pub skills: SkillLibrary,
+    pub items: ItemLibrary,
+    pub injuries: InjuryLibrary,
+}
+
+impl Rules {
+    pub fn load(cache: &AssetCache) -> Rules {
+        Rules {
+            buffs: BuffLibrary::load(cache),
+            formulas: Formulas::load(cache),
+            skills: SkillLibrary::load(cache),
+            items: ItemLibrary::load(cache),
+            injuries: InjuryLibrary::load(cache),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use std::{
+    fs::{self, File},
+    io::prelude::*,
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::locale::FALLBACK_LANGUAGE;
+
+/// Game configuration shared by all saves.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+    pub language: String,
+    /// Delete the save of a game when the player dies.
+    pub permadeath: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            language: FALLBACK_LANGUAGE.to_string(),
+            permadeath: false,
+        }
+    }
+}
+
+impl Settings {
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|p| p.join("wuxia").join("settings.json"))
+    }
+
+    /// Load settings from the data directory. Return default settings if
+    /// the file does not exist or cannot be read.
+    pub fn load() -> Settings {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Some(path) = Self::path() {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).expect("failed to create data directory");
+            }
+
+            let json = serde_json::to_string_pretty(self).expect("failed to parse Settings");
+            let mut file = File::create(&path).expect("failed to create settings file");
+            file.write_all(json.as_bytes())
+                .expect("failed to write to settings file");
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use std::{collections::HashMap, fmt};
+
+use assets_manager::{loader, Asset, AssetCache};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    locale::{trf, Text},
+    Item, ItemCategory, Reputation, Standing,
+};
+
+/// Id of the item used as money. One item is one 文.
+pub const CURRENCY: &str = "copper";
+/// 文 in one 两 of silver.
+const WEN_PER_LIANG: u32 = 1000;
+/// Charisma at which prices are neither raised nor lowered.
+const CHR_BASE: f64 = 10.0;
+/// Part of the price a point of charisma above the base takes off.
+const CHR_DISCOUNT: f64 = 0.02;
+/// Charisma changes prices by at most this part.
+const CHR_LIMIT: f64 = 0.2;
+
+/// An amount of money in 文, shown in 两 and 文.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Money(pub u32);
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (liang, wen) = (self.0 / WEN_PER_LIANG, self.0 % WEN_PER_LIANG);
+        let text = match (liang, wen) {
+            (0, wen) => trf("money.wen", &[&wen.to_string()]),
+            (liang, 0) => trf("money.liang", &[&liang.to_string()]),
+            (liang, wen) => trf("money.liang_wen", &[&liang.to_string(), &wen.to_string()]),
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// Items a merchant has for sale after restocking, e.g.
+/// `{ "item": "dry_rations", "count": 10 }`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockEntry {
+    pub item: String,
+    pub count: u32,
+}
+
+/// A merchant, defined in `assets/merchants.json` and placed on maps.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Merchant {
+    pub id: String,
+    pub name: Text,
+    /// Shown on the map.
+    pub glyph: String,
+    /// Faction whose reputation changes the prices.
+    #[serde(default)]
+    pub faction: Option<String>,
+    pub stock: Vec<StockEntry>,
+    /// Minutes between restocks.
+    pub restock: u32,
+    /// Part of the price the merchant pays for items from the player.
+    pub buy_ratio: f64,
+    /// Categories of items the merchant buys. Empty buys all.
+    #[serde(default)]
+    pub buys: Vec<ItemCategory>,
+}
+
+impl Merchant {
+    /// How the faction of the merchant feels about the player.
+    /// Merchants without a faction are neutral.
+    pub fn standing(&self, reputation: &Reputation) -> Standing {
+        match &self.faction {
+            Some(id) => Standing::of(reputation.get(id)),
+            None => Standing::Neutral,
+        }
+    }
+
+    /// Whether the merchant buys an item at all.
+    pub fn buys(&self, item: &Item) -> bool {
+        item.price > 0
+            && item.category != ItemCategory::Currency
+            && (self.buys.is_empty() || self.buys.contains(&item.category))
+    }
+
+    /// Price the player pays for an item.
+    pub fn selling_price(&self, item: &Item, reputation: &Reputation, chr: usize) -> u32 {
+        let factor = price_factor(self.standing(reputation), chr);
+        ((item.price as f64 * factor).ceil() as u32).max(1)
+    }
+
+    /// Price the merchant pays for an item.
+    pub fn buying_price(&self, item: &Item, reputation: &Reputation, chr: usize) -> u32 {
+        let factor = price_factor(self.standing(reputation), chr);
+        ((item.price as f64 * self.buy_ratio / factor).floor() as u32).max(1)
+    }
+}
+
+/// How much prices are raised for a standing and charisma. Merchants
+/// ask less from those they like and pay them more.
+fn price_factor(standing: Standing, chr: usize) -> f64 {
+    let standing = match standing {
+        Standing::Hostile | Standing::Unfriendly => 1.25,
+        Standing::Neutral => 1.0,
+        Standing::Friendly => 0.9,
+        Standing::Honored => 0.8,
+    };
+    let chr = ((chr as f64 - CHR_BASE) * CHR_DISCOUNT).clamp(-CHR_LIMIT, CHR_LIMIT);
+    standing * (1.0 - chr)
+}
+
+/// All merchants, loaded from `assets/merchants.json`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MerchantLibrary {
+    merchants: Vec<Merchant>,
+}
+
+impl Asset for MerchantLibrary {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+impl MerchantLibrary {
+    pub fn load(cache: &AssetCache) -> MerchantLibrary {
+        let merchant_file = "merchants";
+        let handle = cache.load_expect::<MerchantLibrary>(merchant_file);
+
+        handle.read().to_owned()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Merchant> {
+        self.merchants.iter().find(|m| m.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Merchant> {
+        self.merchants.iter()
+    }
+}
+
+/// What a merchant has left for sale.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Shop {
+    pub stock: Vec<StockEntry>,
+    /// Game minute of the last restock.
+    restocked: u64,
+}
+
+impl Shop {
+    /// Take one item from the stock. Return false if it is sold out.
+    pub fn take(&mut self, id: &str) -> bool {
+        match self.stock.iter_mut().find(|e| e.item == id && e.count > 0) {
+            Some(entry) => {
+                entry.count -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Put an item bought from the player up for sale.
+    pub fn put(&mut self, id: &str) {
+        match self.stock.iter_mut().find(|e| e.item == id) {
+            Some(entry) => entry.count += 1,
+            None => self.stock.push(StockEntry {
+                item: id.to_string(),
+                count: 1,
+            }),
+        }
+    }
+}
+
+/// Stock of every merchant the player traded with, by merchant id.
+/// Merchants not met yet have their full stock.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Shops(HashMap<String, Shop>);
+
+impl Shops {
+    /// Open the shop of a merchant, with a full stock on first visit.
+    pub fn open(&mut self, merchant: &Merchant, now: u64) {
+        self.0.entry(merchant.id.clone()).or_insert_with(|| Shop {
+            stock: merchant.stock.clone(),
+            restocked: now,
+        });
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Shop> {
+        self.0.get(id)
+    }
+
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut Shop> {
+        self.0.get_mut(id)
+    }
+
+    /// Refill the stock of merchants whose restock time has come. Items
+    /// bought from the player are sold by then.
+    pub fn restock(&mut self, library: &MerchantLibrary, now: u64) {
+        for (id, shop) in self.0.iter_mut() {
+            let merchant = match library.get(id) {
+                Some(merchant) => merchant,
+                None => continue,
+            };
+            if now.saturating_sub(shop.restocked) >= merchant.restock as u64 {
+                shop.stock = merchant.stock.clone();
+                shop.restocked = now;
+            }
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use std::{collections::HashMap, fmt};
+
+use assets_manager::{loader, Asset, AssetCache};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    locale::{tr, Text},
+    Attribute, AttributeType, InjuryKind, Modifier, WeaponKind,
+};
+
+/// A martial art (武功), defined in `assets/skills.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Skill {
+    pub id: String,
+    pub name: Text,
+    pub kind: SkillKind,
+    #[serde(default)]
+    pub description: Text,
+    /// Minimum base attributes needed to learn the skill.
+    #[serde(default)]
+    pub requirements: HashMap<AttributeType, usize>,
+    pub max_level: u32,
+    /// Practice needed per level to reach the next level.
+    pub practice: u32,
+    /// Modifiers applied once per skill level.
+    #[serde(default)]
+    pub modifiers: Vec<Modifier>,
+    /// Damage added per level when used in combat.
+    #[serde(default)]
+    pub power: f64,
+    /// Qi spent each time the skill is used in combat.
+    #[serde(default)]
+    pub cost: f64,
+    /// Neili spent each time the skill is used in combat. Enemies do not
+    /// keep track of inner energy and never spend it.
+    #[serde(default)]
+    pub neili: f64,
+    /// Kind of injury a heavy blow with the skill inflicts.
+    #[serde(default)]
+    pub wound: InjuryKind,
+    /// Id of the sect whose members alone can learn the skill.
+    #[serde(default)]
+    pub sect: Option<String>,
+}
+
+impl Skill {
+    /// Requirements that the attributes do not meet.
+    pub fn unmet(&self, attr: &Attribute) -> Vec<(AttributeType, usize)> {
+        AttributeType::ALL
+            .into_iter()
+            .filter_map(|t| self.requirements.get(&t).map(|v| (t, *v)))
+            .filter(|(t, v)| attr.get(*t) < *v)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SkillKind {
+    Fist,
+    Sword,
+    Qinggong,
+    InnerArt,
+    /// Cooking, medicine and other crafts (技艺) used by recipes.
+    Craft,
+}
+
+impl SkillKind {
+    /// Skill can be used to attack in combat.
+    pub fn is_attack(&self) -> bool {
+        matches!(self, SkillKind::Fist | SkillKind::Sword)
+    }
+
+    /// Skill can be used while holding a weapon, or bare-handed.
+    pub fn allows(&self, weapon: Option<WeaponKind>) -> bool {
+        match self {
+            SkillKind::Fist => weapon.is_none(),
+            SkillKind::Sword => weapon == Some(WeaponKind::Sword),
+            SkillKind::Qinggong | SkillKind::InnerArt | SkillKind::Craft => true,
+        }
+    }
+}
+
+impl fmt::Display for SkillKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            tr(match self {
+                SkillKind::Fist => "skill.kind.fist",
+                SkillKind::Sword => "skill.kind.sword",
+                SkillKind::Qinggong => "skill.kind.qinggong",
+                SkillKind::InnerArt => "skill.kind.inner_art",
+                SkillKind::Craft => "skill.kind.craft",
+            })
+        )
+    }
+}
+
+/// A skill learned by a character. The definition stays in the
+/// [`SkillLibrary`], so changes to it reach learned skills.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearnedSkill {
+    /// Skill id.
+    pub id: String,
+    pub level: u32,
+    /// Practice gained towards the next level.
+    pub progress: u32,
+}
+
+impl LearnedSkill {
+    /// Practice needed to reach the next level.
+    pub fn needed(&self, skill: &Skill) -> u32 {
+        skill.practice * self.level.max(1)
+    }
+
+    pub fn is_max(&self, skill: &Skill) -> bool {
+        self.level >= skill.max_level
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Skills(Vec<LearnedSkill>);
+
+impl Skills {
+    /// Learn a skill at level 1. Return false if it is already learned.
+    pub fn learn(&mut self, skill: &Skill) -> bool {
+        self.learn_at(skill, 1)
+    }
+
+    /// Learn a skill at a level, e.g. for enemies.
+    pub fn learn_at(&mut self, skill: &Skill, level: u32) -> bool {
+        if self.contains(&skill.id) {
+            return false;
+        }
+
+        self.0.push(LearnedSkill {
+            id: skill.id.clone(),
+            level: level.clamp(1, skill.max_level.max(1)),
+            progress: 0,
+        });
+        true
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.0.iter().any(|s| s.id == id)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&LearnedSkill> {
+        self.0.iter().find(|s| s.id == id)
+    }
+
+    /// Level of a skill, 0 if it is not learned.
+    pub fn level(&self, id: &str) -> u32 {
+        self.get(id).map_or(0, |s| s.level)
+    }
+
+    /// Learned skills with their definitions. Skills no longer defined
+    /// are left out.
+    pub fn with<'a>(
+        &'a self,
+        library: &'a SkillLibrary,
+    ) -> impl Iterator<Item = (&'a LearnedSkill, &'a Skill)> + Clone {
+        self.0
+            .iter()
+            .filter_map(|s| library.get(&s.id).map(|skill| (s, skill)))
+    }
+
+    /// Sum of levels of all skills of a kind.
+    pub fn total_level(&self, kind: SkillKind, library: &SkillLibrary) -> u32 {
+        self.with(library)
+            .filter(|(_, skill)| skill.kind == kind)
+            .map(|(s, _)| s.level)
+            .sum()
+    }
+
+    /// Skills that can be used to attack in combat.
+    pub fn attacks<'a>(
+        &'a self,
+        library: &'a SkillLibrary,
+    ) -> impl Iterator<Item = (&'a LearnedSkill, &'a Skill)> {
+        self.with(library)
+            .filter(|(_, skill)| skill.kind.is_attack())
+    }
+
+    /// Add practice to a skill. Return the number of levels gained.
+    pub fn practice(&mut self, skill: &Skill, amount: u32) -> u32 {
+        let learned = match self.0.iter_mut().find(|s| s.id == skill.id) {
+            Some(learned) => learned,
+            None => return 0,
+        };
+
+        let mut gained = 0;
+        learned.progress += amount;
+        while !learned.is_max(skill) && learned.progress >= learned.needed(skill) {
+            learned.progress -= learned.needed(skill);
+            learned.level += 1;
+            gained += 1;
+        }
+        if learned.is_max(skill) {
+            learned.progress = 0;
+        }
+        gained
+    }
+
+    /// All modifiers of learned skills with their levels.
+    pub fn modifiers<'a>(
+        &'a self,
+        library: &'a SkillLibrary,
+    ) -> impl Iterator<Item = (&'a Modifier, u32)> + Clone {
+        self.with(library)
+            .flat_map(|(s, skill)| skill.modifiers.iter().map(move |m| (m, s.level)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// All skill definitions.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SkillLibrary {
+    skills: Vec<Skill>,
+}
+
+impl Asset for SkillLibrary {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+impl SkillLibrary {
+    pub fn load(cache: &AssetCache) -> SkillLibrary {
+        let skill_file = "skills";
+        let handle = cache.load_expect::<SkillLibrary>(skill_file);
+
+        handle.read().to_owned()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Skill> {
+        self.skills.iter().find(|s| s.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Skill> {
+        self.skills.iter()
+    }
+}
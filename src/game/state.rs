@@ -1,28 +1,99 @@
 use crate::components::{
-    map::{Maps, Tiles, World},
-    message::MessageSystem,
+    map::{Maps, Step, Tiles, World},
+    message::{MessageSystem, Msg, MsgType, Theme},
     player::Player,
+    Direction, WorldState,
 };
 use assets_manager::AssetCache;
 use serde::{Deserialize, Serialize};
 
-use super::{EventSystem, GameMode, GameSwitch};
+use super::{
+    locale::{tr, trf},
+    settings::Settings,
+    Action, ActiveBuff, Activity, Alignment, AttributeType, Battle, BattleSpec, BuffLibrary,
+    Character, Defeat, DefeatOutcome, EnemyLibrary, EquipSlot, EventCommand, EventSystem,
+    FactionLibrary, Foe, Formulas, GameMode, GameSwitch, GameVariable, Harvests, InjuryChange,
+    InjuryKind, InjuryLibrary, ItemLibrary, Levels, MapDeltas, MerchantLibrary, Money, Outcome,
+    PropertyType, Recipe, RecipeLibrary, Regen, ResourceLibrary, Rules, Shops, SkillKind,
+    SkillLibrary, Standing, CURRENCY,
+};
+
+/// Minutes of game time taken by one step on the map.
+const MINUTES_PER_STEP: u32 = 1;
+/// Minutes of game time taken by one practice of a skill.
+const PRACTICE_MINUTES: u32 = 60;
+/// Shen spent by one practice of a skill.
+const PRACTICE_SHEN: f64 = 10.0;
+/// Experience gained by one practice of a skill.
+const PRACTICE_EXP: u32 = 5;
+/// Minutes rested between checks for anything disturbing the rest.
+const REST_STEP: u32 = 10;
+/// Longest rest, one hour (时辰).
+const REST_MINUTES: u32 = 120;
+/// Longest sleep. Sleep also ends in the morning.
+const SLEEP_MINUTES: u32 = 480;
+/// Longest meditation, one hour (时辰).
+const MEDITATE_MINUTES: u32 = 120;
 
 #[derive(Default, Serialize, Deserialize)]
 pub struct GameState {
+    #[serde(default)]
+    pub battle: Option<Battle>,
     pub curr_map: Option<Maps>,
+    #[serde(skip)]
+    pub defeat: Defeat,
+    #[serde(skip)]
+    pub enemy_library: EnemyLibrary,
     pub event_system: EventSystem,
+    #[serde(skip)]
+    pub faction_library: FactionLibrary,
     pub game_mode: Option<GameMode>,
+    /// The player died and the game waits to go back to the dashboard.
+    #[serde(skip)]
+    pub game_over: bool,
+    #[serde(default)]
+    pub harvests: Harvests,
+    #[serde(skip)]
+    pub levels: Levels,
+    /// Tiles changed while playing, applied on top of the loaded maps.
+    #[serde(default)]
+    pub map_deltas: MapDeltas,
+    #[serde(skip)]
+    pub merchant_library: MerchantLibrary,
     pub messages: MessageSystem,
     #[serde(skip)]
     pub need_update: bool,
     pub player: Player,
     #[serde(skip)]
+    pub recipe_library: RecipeLibrary,
+    #[serde(skip)]
+    pub regen: Regen,
+    #[serde(skip)]
+    pub resource_library: ResourceLibrary,
+    #[serde(skip)]
+    pub rules: Rules,
+    /// Name of the file the game is saved to, chosen on the first save.
+    #[serde(default)]
+    pub save_file: Option<String>,
+    #[serde(skip)]
+    pub settings: Settings,
+    /// Id of the merchant the player trades with.
+    #[serde(skip)]
+    pub shop: Option<String>,
+    #[serde(default)]
+    pub shops: Shops,
+    #[serde(skip)]
     pub should_quit: bool,
     pub switches: GameSwitch,
+    #[serde(skip)]
+    pub theme: Theme,
+    #[serde(default)]
+    pub variables: GameVariable,
     pub visible_range: usize,
     #[serde(skip)]
     pub world_grid: World,
+    #[serde(default)]
+    pub world_state: WorldState,
 }
 
 impl GameState {
@@ -34,28 +105,63 @@ impl GameState {
         }
     }
 
-    pub fn from_json(cache: &AssetCache, json: &str) -> Self {
-        match serde_json::from_str::<GameState>(json) {
-            Ok(mut state) => {
-                state.load(cache);
-                state
-            }
-            Err(_) => panic!("Failed to load from save"),
-        }
+    /// Restore a saved game. Switches, variables and events come from
+    /// the save, everything else from the assets.
+    pub fn from_json(cache: &AssetCache, json: &str) -> serde_json::Result<Self> {
+        let mut state = serde_json::from_str::<GameState>(json)?;
+        state.load_assets(cache);
+        Ok(state)
     }
 
+    /// Load a new game from the assets.
     pub fn load(&mut self, cache: &AssetCache) {
-        self.load_map(cache);
         self.load_events(cache);
         self.load_switch(cache);
+        self.load_variable(cache);
+        self.load_assets(cache);
+    }
+
+    /// Load everything that is not kept in saves and bring the state
+    /// up to date with it.
+    fn load_assets(&mut self, cache: &AssetCache) {
+        self.load_map(cache);
+        self.load_theme(cache);
+        self.load_buffs(cache);
+        self.load_formulas(cache);
+        self.load_regen(cache);
+        self.load_skills(cache);
+        self.load_enemies(cache);
+        self.load_items(cache);
+        self.load_levels(cache);
+        self.load_injuries(cache);
+        self.load_defeat(cache);
+        self.load_factions(cache);
+        self.load_merchants(cache);
+        self.load_recipes(cache);
+        self.load_resources(cache);
 
+        self.player.recompute(&self.rules);
+        self.player.update_level(&self.levels, &self.rules);
         self.update();
     }
 
-    /// Load current map from assets if curr_map is not None.
+    /// Load current map from assets if curr_map is not None, with the
+    /// tiles changed while playing.
     fn load_map(&mut self, cache: &AssetCache) {
         if let Some(map) = &self.curr_map {
             self.world_grid = World::load(cache, map);
+            self.map_deltas.apply(map.map_file(), &mut self.world_grid);
+        }
+    }
+
+    /// Replace a tile of the current map and remember it in the save.
+    fn change_tile(&mut self, pos: (usize, usize), tile: usize) {
+        let map = match &self.curr_map {
+            Some(map) => map.map_file(),
+            None => return,
+        };
+        if self.world_grid.set_tile(pos, tile) {
+            self.map_deltas.set(map, pos, tile);
         }
     }
 
@@ -63,16 +169,77 @@ impl GameState {
         self.switches = GameSwitch::load(cache);
     }
 
+    fn load_variable(&mut self, cache: &AssetCache) {
+        self.variables = GameVariable::load(cache);
+    }
+
+    fn load_theme(&mut self, cache: &AssetCache) {
+        self.theme = Theme::load(cache);
+    }
+
+    fn load_buffs(&mut self, cache: &AssetCache) {
+        self.rules.buffs = BuffLibrary::load(cache);
+    }
+
+    fn load_formulas(&mut self, cache: &AssetCache) {
+        self.rules.formulas = Formulas::load(cache);
+    }
+
+    fn load_regen(&mut self, cache: &AssetCache) {
+        self.regen = Regen::load(cache);
+    }
+
+    fn load_injuries(&mut self, cache: &AssetCache) {
+        self.rules.injuries = InjuryLibrary::load(cache);
+    }
+
+    fn load_defeat(&mut self, cache: &AssetCache) {
+        self.defeat = Defeat::load(cache);
+    }
+
+    fn load_factions(&mut self, cache: &AssetCache) {
+        self.faction_library = FactionLibrary::load(cache);
+    }
+
+    fn load_merchants(&mut self, cache: &AssetCache) {
+        self.merchant_library = MerchantLibrary::load(cache);
+    }
+
+    fn load_recipes(&mut self, cache: &AssetCache) {
+        self.recipe_library = RecipeLibrary::load(cache);
+    }
+
+    fn load_resources(&mut self, cache: &AssetCache) {
+        self.resource_library = ResourceLibrary::load(cache);
+    }
+
+    fn load_skills(&mut self, cache: &AssetCache) {
+        self.rules.skills = SkillLibrary::load(cache);
+    }
+
+    fn load_enemies(&mut self, cache: &AssetCache) {
+        self.enemy_library = EnemyLibrary::load(cache);
+    }
+
+    fn load_items(&mut self, cache: &AssetCache) {
+        self.rules.items = ItemLibrary::load(cache);
+    }
+
+    fn load_levels(&mut self, cache: &AssetCache) {
+        self.levels = Levels::load(cache);
+    }
+
     // Load all events from assets
     pub fn load_events(&mut self, cache: &AssetCache) {
         self.event_system = EventSystem::load(cache);
     }
 
     pub fn update(&mut self) {
+        // commands of events may ask for another update
+        self.need_update = false;
+
         // update events status
         self.update_events();
-
-        self.need_update = false;
     }
 
     fn update_events(&mut self) {
@@ -80,34 +247,1107 @@ impl GameState {
             let waiting_events = self.event_system.get_waiting();
             if !waiting_events.is_empty() {
                 for e in waiting_events {
-                    e.ready(&self.switches);
+                    e.ready(&self.switches, self.player.reputation());
                 }
             }
         }
 
+        let mut commands = vec![];
         {
             let ready_events = self.event_system.get_ready();
             if !ready_events.is_empty() {
                 for e in ready_events {
                     e.run(&mut self.messages);
+                    commands.extend(e.commands().iter().cloned());
+                }
+            }
+        }
+
+        for command in commands {
+            self.execute(command);
+        }
+    }
+
+    /// Run an event command on the game state.
+    pub fn execute(&mut self, command: EventCommand) {
+        match command {
+            EventCommand::AddBuff(id) => self.apply_buff(&id, &tr("buff.source.event")),
+            EventCommand::RemoveBuff(id) => self.player.remove_buff(&id, &self.rules),
+            EventCommand::SetSwitch(name, on) => {
+                self.switches.set(&name, on);
+                self.need_update = true;
+            }
+            EventCommand::SetVariable(name, value) => self.variables.set(&name, value),
+            EventCommand::LearnSkill(id) => self.learn_skill(&id),
+            EventCommand::StartBattle(spec) => self.start_battle(spec, None),
+            EventCommand::GiveItem(id, count) => self.give_item(&id, count),
+            EventCommand::TakeItem(id, count) => self.take_item(&id, count),
+            EventCommand::GiveExp(amount) => self.give_exp(amount),
+            EventCommand::Injure(kind, severity) => self.injure(kind, severity),
+            EventCommand::ChangeReputation(id, delta) => self.change_reputation(&id, delta),
+            EventCommand::ChangeAlignment(delta) => self.change_alignment(delta),
+            EventCommand::JoinSect(id) => self.join_sect(&id),
+            EventCommand::SetTile(x, y, tile) => self.change_tile((x, y), tile),
+        }
+    }
+
+    /// Give the player items from the library.
+    pub fn give_item(&mut self, id: &str, count: u32) {
+        let msgs = self
+            .receive_item(id, count)
+            .iter()
+            .map(|text| Msg::markup(MsgType::System, text))
+            .collect();
+        self.messages.add_sentences(msgs);
+    }
+
+    /// Put items in the inventory. Return what to tell the player.
+    fn receive_item(&mut self, id: &str, count: u32) -> Vec<String> {
+        let item = match self.rules.items.get(id) {
+            Some(item) => item,
+            None => return vec![],
+        };
+
+        let added = self.player.give_item(item, count, &self.rules);
+        let mut lines = vec![];
+        if added > 0 {
+            lines.push(trf("item.received", &[item.name.get(), &added.to_string()]));
+        }
+        if added < count {
+            lines.push(trf("item.too_heavy", &[item.name.get()]));
+        }
+        lines
+    }
+
+    /// Give the player experience.
+    pub fn give_exp(&mut self, amount: u32) {
+        let msgs = self
+            .receive_exp(amount)
+            .iter()
+            .map(|text| Msg::markup(MsgType::System, text))
+            .collect();
+        self.messages.add_sentences(msgs);
+    }
+
+    /// Injure the player with some severity.
+    pub fn injure(&mut self, kind: InjuryKind, severity: u32) {
+        let rule = match self.rules.injuries.get(kind) {
+            Some(rule) => rule,
+            None => return,
+        };
+
+        let severity = self.player.injure(rule, severity, &self.rules);
+        let text = trf(
+            "injury.inflicted",
+            &[&kind.to_string(), &severity.to_string()],
+        );
+        self.messages
+            .add_sentence(Msg::markup(MsgType::System, &text));
+    }
+
+    /// Add experience and level up. Return what to tell the player.
+    fn receive_exp(&mut self, amount: u32) -> Vec<String> {
+        if amount == 0 {
+            return vec![];
+        }
+
+        let gained = self.player.gain_exp(amount, &self.levels, &self.rules);
+        let mut lines = vec![trf("level.exp", &[&amount.to_string()])];
+        if gained > 0 {
+            lines.push(trf(
+                "level.up",
+                &[
+                    &self.player.level().to_string(),
+                    &self.player.points().to_string(),
+                ],
+            ));
+        }
+        lines
+    }
+
+    /// Spend an attribute point.
+    pub fn raise_attr(&mut self, attr: AttributeType) {
+        let text = if self.player.raise_attr(attr, &self.rules) {
+            trf(
+                "level.raised",
+                &[&attr.to_string(), &self.player.attr().get(attr).to_string()],
+            )
+        } else {
+            tr("level.no_points")
+        };
+        self.messages
+            .add_sentence(Msg::markup(MsgType::System, &text));
+    }
+
+    pub fn take_item(&mut self, id: &str, count: u32) {
+        let taken = self.player.take_item(id, count);
+        if let (Some(item), true) = (self.rules.items.get(id), taken > 0) {
+            let text = trf("item.lost", &[item.name.get(), &taken.to_string()]);
+            self.messages
+                .add_sentence(Msg::markup(MsgType::System, &text));
+        }
+    }
+
+    /// Use one item of an inventory slot. Return what happened, or why
+    /// the item cannot be used.
+    pub fn use_item(&mut self, index: usize) -> Result<String, String> {
+        let stack = self.player.inventory().get(index);
+        let item = match stack.and_then(|s| self.rules.items.get(&s.item)) {
+            Some(item) => item.clone(),
+            None => return Err(tr("item.none")),
+        };
+        let effect = match &item.effect {
+            Some(effect) => effect,
+            None => return Err(trf("item.unusable", &[item.name.get()])),
+        };
+
+        self.player.take_item(&item.id, 1);
+        for (prop, amount) in effect.restore.iter() {
+            self.player.change_prop(*prop, *amount);
+        }
+        for id in effect.buffs.iter() {
+            self.apply_buff(id, item.name.get());
+        }
+        for (kind, amount) in effect.cure.iter() {
+            self.player.treat(*kind, *amount, &self.rules);
+        }
+
+        Ok(trf("item.used", &[item.name.get()]))
+    }
+
+    /// Equip an item from an inventory slot.
+    pub fn equip(&mut self, index: usize) {
+        let text = match self.player.equip(index, &self.rules) {
+            Some(item) => trf("equip.equipped", &[item.name.get()]),
+            None => tr("equip.cannot"),
+        };
+        self.messages
+            .add_sentence(Msg::markup(MsgType::System, &text));
+    }
+
+    pub fn unequip(&mut self, slot: EquipSlot) {
+        if self.player.equipment().get(slot).is_none() {
+            return;
+        }
+
+        let text = match self.player.unequip(slot, &self.rules) {
+            Some(item) => trf("equip.unequipped", &[item.name.get()]),
+            None => tr("equip.no_room"),
+        };
+        self.messages
+            .add_sentence(Msg::markup(MsgType::System, &text));
+    }
+
+    /// Throw away a whole inventory slot.
+    pub fn drop_item(&mut self, index: usize) {
+        if let Some(id) = self.player.drop_slot(index) {
+            let name = self
+                .rules
+                .items
+                .get(&id)
+                .map_or(id.clone(), |i| i.name.to_string());
+            self.messages
+                .add_sentence(Msg::markup(MsgType::System, &trf("item.dropped", &[&name])));
+        }
+    }
+
+    /// Move the player on the map. Walking into a creature fights it,
+    /// walking into a merchant trades with them and walking into a
+    /// closed door opens it instead.
+    pub fn move_player(&mut self, direction: Direction) {
+        let target = self.world_grid.neighbor(self.player.get_pos(), &direction);
+        let door = target.and_then(|pos| {
+            let tile = self.world_grid.tile_at(pos)?;
+            Some((pos, tile.opens?, tile.name.to_string()))
+        });
+        if let Some((pos, opened, name)) = door {
+            self.change_tile(pos, opened);
+            self.messages
+                .add_sentence(Msg::markup(MsgType::System, &trf("door.opened", &[&name])));
+            self.end_turn();
+        } else if let Some(stall) = target.and_then(|pos| self.world_grid.stall_at(pos)) {
+            let id = stall.merchant.clone();
+            self.open_shop(&id);
+        } else if let Some(creature) = target.and_then(|pos| self.world_grid.creature_at(pos)) {
+            let spec = BattleSpec {
+                enemies: vec![creature.enemy.clone()],
+                win: None,
+                lose: None,
+            };
+            self.start_battle(spec, target);
+        } else {
+            match self
+                .world_grid
+                .player_move(&mut self.player, direction, &self.switches)
+            {
+                Step::Moved => {
+                    self.end_turn();
+                    self.world_grid.spawn(self.player.get_pos());
+                }
+                Step::Refused(text) => self
+                    .messages
+                    .add_sentence(Msg::markup(MsgType::System, &text)),
+                Step::Blocked => {}
+            }
+        }
+    }
+
+    /// Start a battle against enemies from the library. Unknown enemies
+    /// and those whose formulas fail are left out. A creature fought on
+    /// the map leaves it when beaten.
+    fn start_battle(&mut self, spec: BattleSpec, creature: Option<(usize, usize)>) {
+        let mut errors = vec![];
+        let foes: Vec<Foe> = spec
+            .enemies
+            .iter()
+            .filter_map(|id| self.enemy_library.get(id))
+            .filter_map(|e| {
+                Foe::new(e, &self.rules)
+                    .map_err(|error| errors.push(format!("{}: {}", e.id, error)))
+                    .ok()
+            })
+            .collect();
+        self.report_formula_errors(errors);
+        if foes.is_empty() {
+            return;
+        }
+
+        let names: Vec<&str> = foes.iter().map(|f| f.name.get()).collect();
+        let text = trf("battle.start", &[&names.join("、")]);
+        self.battle_log(vec![text]);
+
+        // foes faster than the player act first
+        let mut battle = Battle::new(spec, foes, &self.player);
+        battle.creature = creature;
+        let lines = battle.foes_act(&mut self.player, &self.rules);
+        self.battle_log(lines);
+        self.resolve_battle(battle);
+    }
+
+    /// Take an action in the current battle, then let foes act.
+    pub fn battle_action(&mut self, action: Action) {
+        let mut battle = match self.battle.take() {
+            Some(battle) => battle,
+            None => return,
+        };
+
+        // items need the libraries, so they are used here instead
+        let (lines, used) = match action {
+            Action::Item(index) => match self.use_item(index) {
+                Ok(text) => {
+                    battle.skip_turn(&self.player);
+                    (vec![text], true)
+                }
+                Err(text) => (vec![text], false),
+            },
+            _ => battle.player_act(&action, &mut self.player, &self.rules),
+        };
+        self.battle_log(lines);
+        if used {
+            // a defeat in battle is left to resolve_battle
+            self.pass_turn();
+            let lines = battle.foes_act(&mut self.player, &self.rules);
+            self.battle_log(lines);
+        }
+        self.resolve_battle(battle);
+    }
+
+    /// Keep the battle going, or end it and report the outcome to switches.
+    fn resolve_battle(&mut self, battle: Battle) {
+        let outcome = match battle.outcome(&self.player) {
+            Some(outcome) => outcome,
+            None => {
+                self.battle = Some(battle);
+                return;
+            }
+        };
+
+        let switch = match outcome {
+            Outcome::Victory => battle.spec().win.clone(),
+            Outcome::Defeat => battle.spec().lose.clone(),
+            Outcome::Fled => None,
+        };
+        if let (Outcome::Victory, Some(pos)) = (outcome, battle.creature) {
+            self.world_grid.remove_creature(pos);
+        }
+        if let Some(switch) = switch {
+            self.switches.set(&switch, true);
+            self.need_update = true;
+        }
+
+        let text = tr(match outcome {
+            Outcome::Victory => "battle.victory",
+            Outcome::Defeat => "battle.defeat",
+            Outcome::Fled => "battle.escaped",
+        });
+        self.battle_log(vec![text]);
+
+        if outcome == Outcome::Defeat {
+            self.defeat();
+        }
+
+        if outcome == Outcome::Victory {
+            let exp = battle
+                .foes
+                .iter()
+                .filter(|f| f.is_beaten())
+                .filter_map(|f| self.enemy_library.get(&f.id))
+                .map(|e| e.exp)
+                .sum();
+            let lines = self.receive_exp(exp);
+            self.battle_log(lines);
+
+            let loot: Vec<(String, u32)> = battle
+                .foes
+                .iter()
+                .filter(|f| f.is_beaten())
+                .filter_map(|f| self.enemy_library.get(&f.id))
+                .flat_map(|e| e.roll_loot())
+                .collect();
+            for (id, count) in loot {
+                let lines = self.receive_item(&id, count);
+                self.battle_log(lines);
+            }
+        }
+    }
+
+    /// Handle the player being beaten as the first defeat rule that
+    /// applies decides.
+    fn defeat(&mut self) {
+        let severity = self.player.injuries().worst();
+        match self.defeat.outcome(&self.switches, severity).clone() {
+            DefeatOutcome::Faint => self.faint(),
+            DefeatOutcome::Event(switch) => {
+                self.player.revive(self.defeat.faint.recover);
+                self.switches.set(&switch, true);
+                self.need_update = true;
+            }
+            DefeatOutcome::Death => {
+                self.game_over = true;
+                let key = if self.settings.permadeath {
+                    "defeat.permadeath"
+                } else {
+                    "defeat.death"
+                };
+                self.messages
+                    .add_sentence(Msg::markup(MsgType::System, &tr(key)));
+            }
+        }
+    }
+
+    /// Faint and wake up at a safe place, losing some experience and
+    /// items.
+    fn faint(&mut self) {
+        let faint = self.defeat.faint.clone();
+        self.pass_time(faint.minutes, Activity::Active);
+        self.player.revive(faint.recover);
+        if let Some(spawn) = self.world_grid.spawn {
+            self.player.set_pos(spawn);
+        }
+
+        let mut lines = vec![trf("defeat.faint", &[&self.world_state.clock.to_string()])];
+        let lost = self.player.lose_exp(faint.exp_loss, &self.levels);
+        if lost > 0 {
+            lines.push(trf("defeat.exp_lost", &[&lost.to_string()]));
+        }
+        for (id, ratio) in faint.items.iter() {
+            let count = (self.player.inventory().count(id) as f64 * ratio).floor() as u32;
+            let name = self.rules.items.get(id).map(|i| i.name.to_string());
+            if let (true, Some(name)) = (count > 0, name) {
+                self.player.take_item(id, count);
+                lines.push(trf("defeat.item_lost", &[&name, &count.to_string()]));
+            }
+        }
+        let msgs = lines
+            .iter()
+            .map(|text| Msg::markup(MsgType::System, text))
+            .collect();
+        self.messages.add_sentences(msgs);
+
+        for (kind, severity) in faint.injuries {
+            self.injure(kind, severity);
+        }
+    }
+
+    /// Tell the player about formulas that failed to evaluate.
+    fn report_formula_errors(&mut self, errors: Vec<String>) {
+        let msgs = errors
+            .iter()
+            .map(|e| Msg::markup(MsgType::System, &trf("formula.error", &[e])))
+            .collect();
+        self.messages.add_sentences(msgs);
+    }
+
+    fn battle_log(&mut self, lines: Vec<String>) {
+        let msgs = lines
+            .iter()
+            .map(|line| Msg::markup(MsgType::Battle, line))
+            .collect();
+        self.messages.add_sentences(msgs);
+    }
+
+    /// Teach the player a skill from the library if the requirements
+    /// are met.
+    pub fn learn_skill(&mut self, id: &str) {
+        let skill = match self.rules.skills.get(id) {
+            Some(skill) => skill,
+            None => return,
+        };
+
+        let sect = skill
+            .sect
+            .as_ref()
+            .filter(|s| self.player.reputation().sect() != Some(s.as_str()));
+        let unmet = skill.unmet(self.player.attr());
+        let text = if let Some(sect) = sect {
+            let sect = self
+                .faction_library
+                .get(sect)
+                .map_or(sect.as_str(), |f| f.name.get());
+            trf("skill.sect_only", &[skill.name.get(), sect])
+        } else if !unmet.is_empty() {
+            let unmet: Vec<String> = unmet.iter().map(|(t, v)| format!("{} {}", t, v)).collect();
+            trf("skill.unmet", &[skill.name.get(), &unmet.join(", ")])
+        } else if self.player.learn_skill(skill, &self.rules) {
+            trf("skill.learned", &[skill.name.get()])
+        } else {
+            return;
+        };
+        self.messages
+            .add_sentence(Msg::markup(MsgType::System, &text));
+    }
+
+    /// Change the reputation with a faction.
+    pub fn change_reputation(&mut self, id: &str, delta: i32) {
+        let name = match self.faction_library.get(id) {
+            Some(faction) => faction.name.to_string(),
+            None => return,
+        };
+
+        let value = self.player.reputation_mut().change(id, delta);
+        let key = if delta >= 0 {
+            "reputation.raised"
+        } else {
+            "reputation.lowered"
+        };
+        let text = trf(key, &[&name, &Standing::of(value).to_string()]);
+        self.messages
+            .add_sentence(Msg::markup(MsgType::System, &text));
+        self.need_update = true;
+    }
+
+    /// Move the alignment toward 正 (positive) or 邪 (negative).
+    pub fn change_alignment(&mut self, delta: i32) {
+        let before = Alignment::of(self.player.reputation().alignment());
+        let after = Alignment::of(self.player.reputation_mut().shift_alignment(delta));
+        if before != after {
+            let text = trf("alignment.changed", &[&after.to_string()]);
+            self.messages
+                .add_sentence(Msg::markup(MsgType::System, &text));
+        }
+        self.need_update = true;
+    }
+
+    /// Join a sect and learn all of its martial arts that the player
+    /// can.
+    pub fn join_sect(&mut self, id: &str) {
+        let name = match self.faction_library.get(id) {
+            Some(faction) if faction.sect => faction.name.to_string(),
+            _ => return,
+        };
+
+        if !self.player.reputation_mut().join(id) {
+            self.messages
+                .add_sentence(Msg::markup(MsgType::System, &tr("faction.already_member")));
+            return;
+        }
+        self.messages.add_sentence(Msg::markup(
+            MsgType::System,
+            &trf("faction.joined", &[&name]),
+        ));
+
+        let skills: Vec<String> = self
+            .rules
+            .skills
+            .iter()
+            .filter(|s| s.sect.as_deref() == Some(id))
+            .map(|s| s.id.clone())
+            .collect();
+        for skill in skills {
+            self.learn_skill(&skill);
+        }
+        self.need_update = true;
+    }
+
+    /// Start trading with a merchant, unless their faction is hostile.
+    pub fn open_shop(&mut self, id: &str) {
+        let merchant = match self.merchant_library.get(id) {
+            Some(merchant) => merchant,
+            None => return,
+        };
+
+        if merchant.standing(self.player.reputation()) == Standing::Hostile {
+            let text = trf("shop.refused", &[merchant.name.get()]);
+            self.messages
+                .add_sentence(Msg::markup(MsgType::System, &text));
+            return;
+        }
+        let now = self.world_state.clock.total_minutes();
+        self.shops.open(merchant, now);
+        self.shop = Some(id.to_string());
+    }
+
+    pub fn close_shop(&mut self) {
+        self.shop = None;
+    }
+
+    /// Buy one item from a row of the open shop.
+    pub fn buy(&mut self, index: usize) {
+        let merchant = match self
+            .shop
+            .as_ref()
+            .and_then(|id| self.merchant_library.get(id))
+        {
+            Some(merchant) => merchant,
+            None => return,
+        };
+        let item = match self
+            .shops
+            .get(&merchant.id)
+            .and_then(|s| s.stock.get(index))
+            .and_then(|e| self.rules.items.get(&e.item).filter(|_| e.count > 0))
+        {
+            Some(item) => item,
+            None => return,
+        };
+
+        let chr = self.player.effective_attr().get(AttributeType::Chr);
+        let price = merchant.selling_price(item, self.player.reputation(), chr);
+        let text = if self.player.inventory().count(CURRENCY) < price {
+            tr("shop.no_money")
+        } else if self.player.give_item(item, 1, &self.rules) == 0 {
+            tr("shop.no_room")
+        } else {
+            self.player.take_item(CURRENCY, price);
+            if let Some(shop) = self.shops.get_mut(&merchant.id) {
+                shop.take(&item.id);
+            }
+            trf("shop.bought", &[item.name.get(), &Money(price).to_string()])
+        };
+        self.messages
+            .add_sentence(Msg::markup(MsgType::System, &text));
+    }
+
+    /// Sell one item from an inventory slot to the open shop.
+    pub fn sell(&mut self, index: usize) {
+        let merchant = match self
+            .shop
+            .as_ref()
+            .and_then(|id| self.merchant_library.get(id))
+        {
+            Some(merchant) => merchant,
+            None => return,
+        };
+        let stack = self.player.inventory().get(index);
+        let item = match stack.and_then(|s| self.rules.items.get(&s.item)) {
+            Some(item) => item.clone(),
+            None => return,
+        };
+        let currency = match self.rules.items.get(CURRENCY) {
+            Some(currency) => currency,
+            None => return,
+        };
+
+        let chr = self.player.effective_attr().get(AttributeType::Chr);
+        let price = merchant.buying_price(&item, self.player.reputation(), chr);
+        let text = if !merchant.buys(&item) {
+            trf("shop.unwanted", &[merchant.name.get(), item.name.get()])
+        } else {
+            // money weighs nothing, but a full pack may have no slot for it
+            self.player.take_item(&item.id, 1);
+            let paid = self.player.give_item(currency, price, &self.rules);
+            if paid < price {
+                self.player.take_item(CURRENCY, paid);
+                self.player.give_item(&item, 1, &self.rules);
+                tr("shop.no_room")
+            } else {
+                if let Some(shop) = self.shops.get_mut(&merchant.id) {
+                    shop.put(&item.id);
                 }
+                trf("shop.sold", &[item.name.get(), &Money(price).to_string()])
+            }
+        };
+        self.messages
+            .add_sentence(Msg::markup(MsgType::System, &text));
+    }
+
+    /// Why a recipe cannot be made here and now, if it cannot.
+    pub fn craft_problem(&self, recipe: &Recipe) -> Option<String> {
+        let item_name = |id: &str| {
+            self.rules
+                .items
+                .get(id)
+                .map_or(id.to_string(), |i| i.name.to_string())
+        };
+        let inventory = self.player.inventory();
+
+        let mut skills: Vec<(&String, &u32)> = recipe.skills.iter().collect();
+        skills.sort();
+        for (id, level) in skills {
+            if self.player.skills().level(id) < *level {
+                let name = self
+                    .rules
+                    .skills
+                    .get(id)
+                    .map_or(id.as_str(), |s| s.name.get());
+                return Some(trf("craft.skill", &[name, &level.to_string()]));
+            }
+        }
+        if let Some(station) = &recipe.station {
+            if !self
+                .world_grid
+                .stations_near(self.player.get_pos())
+                .contains(&station.as_str())
+            {
+                let name = self.world_grid.station_name(station).unwrap_or(station);
+                return Some(trf("craft.no_station", &[name]));
             }
         }
+        if let Some(id) = recipe.tools.iter().find(|id| inventory.count(id) == 0) {
+            return Some(trf("craft.tool", &[&item_name(id)]));
+        }
+        for input in recipe.inputs.iter() {
+            let have = inventory.count(&input.item);
+            if have < input.count {
+                let missing = (input.count - have).to_string();
+                return Some(trf("craft.missing", &[&item_name(&input.item), &missing]));
+            }
+        }
+        None
+    }
+
+    /// Make a recipe from the library, spending its inputs and time.
+    pub fn craft(&mut self, index: usize) {
+        let recipe = match self.recipe_library.get(index) {
+            Some(recipe) => recipe.clone(),
+            None => return,
+        };
+        if let Some(text) = self.craft_problem(&recipe) {
+            self.messages
+                .add_sentence(Msg::markup(MsgType::System, &text));
+            return;
+        }
+
+        for input in recipe.inputs.iter() {
+            self.player.take_item(&input.item, input.count);
+        }
+        self.pass_time(recipe.minutes, Activity::Active);
+        let text = trf(
+            "craft.made",
+            &[recipe.name.get(), &recipe.minutes.to_string()],
+        );
+        self.messages
+            .add_sentence(Msg::markup(MsgType::System, &text));
+
+        let mut lines = vec![];
+        for output in recipe.outputs.iter() {
+            lines.extend(self.receive_item(&output.item, output.count));
+        }
+        for id in recipe.skills.keys() {
+            lines.extend(self.train(id));
+        }
+        let msgs = lines
+            .iter()
+            .map(|text| Msg::markup(MsgType::System, text))
+            .collect();
+        self.messages.add_sentences(msgs);
+        self.check_beaten();
+    }
+
+    /// Gather the first resource node on or next to the player that has
+    /// respawned, spending time and Jing.
+    pub fn gather(&mut self) {
+        let map = match &self.curr_map {
+            Some(map) => map.map_file().to_string(),
+            None => return,
+        };
+        let now = self.world_state.clock.total_minutes();
+        let nodes = self.world_grid.resources_near(self.player.get_pos());
+        let found = nodes
+            .iter()
+            .find(|(pos, _)| !self.harvests.is_depleted(&map, *pos, now))
+            .and_then(|(pos, id)| Some((*pos, self.resource_library.get(id)?.clone())));
+
+        let jing = self.player.prop().number(&PropertyType::Jing);
+        let (pos, node) = match found {
+            Some((pos, node)) if jing > node.jing => (pos, node),
+            found => {
+                let key = if nodes.is_empty() {
+                    "gather.nothing_here"
+                } else if found.is_none() {
+                    "gather.depleted"
+                } else {
+                    "gather.too_tired"
+                };
+                self.messages
+                    .add_sentence(Msg::markup(MsgType::System, &tr(key)));
+                return;
+            }
+        };
+
+        self.player.change_prop(PropertyType::Jing, -node.jing);
+        self.pass_time(node.minutes, Activity::Active);
+        match node.leaves {
+            Some(tile) => self.change_tile(pos, tile),
+            None => {
+                let ready = self.world_state.clock.total_minutes() + node.respawn as u64;
+                self.harvests.deplete(&map, pos, ready);
+            }
+        }
+
+        let level = node
+            .skill
+            .as_ref()
+            .map_or(0, |id| self.player.skills().level(id));
+        let found = node.roll(level);
+        let key = if found.is_empty() {
+            "gather.nothing"
+        } else {
+            "gather.done"
+        };
+        let text = trf(key, &[node.name.get(), &node.minutes.to_string()]);
+        self.messages
+            .add_sentence(Msg::markup(MsgType::System, &text));
+
+        let mut lines = vec![];
+        for (id, count) in found {
+            lines.extend(self.receive_item(&id, count));
+        }
+        if let Some(id) = &node.skill {
+            lines.extend(self.train(id));
+        }
+        let msgs = lines
+            .iter()
+            .map(|text| Msg::markup(MsgType::System, text))
+            .collect();
+        self.messages.add_sentences(msgs);
+        self.check_beaten();
+    }
+
+    /// Practice a learned skill by using it. Return what to tell the
+    /// player when it levels up.
+    fn train(&mut self, id: &str) -> Option<String> {
+        if self.player.practice_skill(id, 1, &self.rules) == 0 {
+            return None;
+        }
+        let name = self.rules.skills.get(id).map_or(id, |s| s.name.get());
+        Some(trf(
+            "skill.level_up",
+            &[name, &self.player.skills().level(id).to_string()],
+        ))
+    }
+
+    /// Practice a learned skill, spending time and Shen.
+    pub fn practice(&mut self, id: &str) {
+        let (learned, skill) = match self.player.skills().get(id).zip(self.rules.skills.get(id)) {
+            Some(found) => found,
+            None => return,
+        };
+        let name = skill.name.to_string();
+
+        let refusal = if learned.is_max(skill) {
+            Some(trf("skill.mastered", &[&name]))
+        } else if self.player.prop().number(&PropertyType::Shen) < PRACTICE_SHEN {
+            Some(tr("skill.too_tired"))
+        } else {
+            None
+        };
+        if let Some(text) = refusal {
+            self.messages
+                .add_sentence(Msg::markup(MsgType::System, &text));
+            return;
+        }
+
+        // smarter characters learn faster
+        let amount = 1 + self.player.effective_attr().get(AttributeType::Int) as u32 / 5;
+        self.player.change_prop(PropertyType::Shen, -PRACTICE_SHEN);
+        let gained = self.player.practice_skill(id, amount, &self.rules);
+        self.pass_time(PRACTICE_MINUTES, Activity::Active);
+
+        let text = match gained {
+            0 => trf("skill.practiced", &[&name]),
+            _ => trf(
+                "skill.level_up",
+                &[&name, &self.player.skills().level(id).to_string()],
+            ),
+        };
+        self.messages
+            .add_sentence(Msg::markup(MsgType::System, &text));
+        self.give_exp(PRACTICE_EXP);
+    }
+
+    /// Apply a buff from the library to the player.
+    pub fn apply_buff(&mut self, id: &str, source: &str) {
+        if let Some(buff) = self.rules.buffs.get(id) {
+            self.player.add_buff(buff, source, &self.rules);
+            self.messages.add_sentence(Msg::markup(
+                MsgType::System,
+                &trf("buff.applied", &[buff.name.get()]),
+            ));
+        }
+    }
+
+    /// Let game time pass while the player does something.
+    pub fn pass_time(&mut self, minutes: u32, activity: Activity) {
+        let night = self.world_state.clock.hour.is_night();
+        self.world_state.clock.advance(minutes);
+        let now = self.world_state.clock.total_minutes();
+        self.shops.restock(&self.merchant_library, now);
+        self.harvests.respawn(now);
+        self.player
+            .regenerate(&self.regen, &self.rules, activity, night, minutes);
+        let expired = self.player.pass_minutes(minutes, &self.rules);
+        self.report_expired(expired);
+        for (kind, change) in self.player.tend_injuries(minutes, activity, &self.rules) {
+            let key = match change {
+                InjuryChange::Worse => "injury.worse",
+                InjuryChange::Better => "injury.better",
+                InjuryChange::Healed => "injury.healed",
+            };
+            let severity = self.player.injuries().severity(kind);
+            let text = trf(key, &[&kind.to_string(), &severity.to_string()]);
+            self.messages
+                .add_sentence(Msg::markup(MsgType::System, &text));
+        }
+    }
+
+    /// Rest, sleep at home or meditate. Rest ends when fully recovered
+    /// and sleep in the morning. All end early when an event starts or
+    /// a creature shows up nearby.
+    pub fn rest(&mut self, activity: Activity) {
+        let pos = self.player.get_pos();
+        let sleeping = activity == Activity::Sleeping;
+        let resting = activity == Activity::Resting;
+        let refusal = match activity {
+            Activity::Sleeping if !self.world_grid.is_home(pos) => Some("rest.not_home"),
+            Activity::Resting
+                if self.player.is_recovered() && self.player.injuries().is_empty() =>
+            {
+                Some("rest.not_needed")
+            }
+            Activity::Meditating
+                if self
+                    .player
+                    .skills()
+                    .total_level(SkillKind::InnerArt, &self.rules.skills)
+                    == 0 =>
+            {
+                Some("rest.no_inner_art")
+            }
+            _ => None,
+        };
+        if let Some(key) = refusal {
+            self.messages
+                .add_sentence(Msg::markup(MsgType::System, &tr(key)));
+            return;
+        }
+
+        let limit = match activity {
+            Activity::Sleeping => SLEEP_MINUTES,
+            Activity::Meditating => MEDITATE_MINUTES,
+            _ => REST_MINUTES,
+        };
+        let nearby = self
+            .world_grid
+            .creatures_near(pos, self.visible_range)
+            .len();
+        let mut rested = 0;
+        let disturbed = loop {
+            let recovered = self.player.is_recovered() && self.player.injuries().is_empty();
+            if rested >= limit || (resting && recovered) {
+                break None;
+            }
+
+            let was_night = self.world_state.clock.hour.is_night();
+            let minutes = REST_STEP.min(limit - rested);
+            self.pass_time(minutes, activity);
+            rested += minutes;
+            self.world_grid.spawn(pos);
+            if self.need_update {
+                self.update();
+            }
+
+            if self.battle.is_some() || self.messages.is_pending() {
+                break Some(tr("rest.event"));
+            }
+            let creatures = self.world_grid.creatures_near(pos, self.visible_range);
+            if creatures.len() > nearby {
+                let name = creatures
+                    .last()
+                    .and_then(|c| self.enemy_library.get(&c.enemy))
+                    .map_or(String::new(), |e| e.name.to_string());
+                break Some(trf("rest.disturbed", &[&name]));
+            }
+            if sleeping && was_night && !self.world_state.clock.hour.is_night() {
+                break None;
+            }
+        };
+
+        if let Some(text) = disturbed {
+            self.messages
+                .add_sentence(Msg::markup(MsgType::System, &text));
+        }
+        let key = match activity {
+            Activity::Sleeping => "rest.slept",
+            Activity::Meditating => "rest.meditated",
+            _ => "rest.rested",
+        };
+        let max_neili = self.player.prop().number(&PropertyType::MaxNeili);
+        let text = trf(
+            key,
+            &[
+                &rested.to_string(),
+                &self.world_state.clock.to_string(),
+                &max_neili.to_string(),
+            ],
+        );
+        self.messages
+            .add_sentence(Msg::markup(MsgType::System, &text));
+        self.check_beaten();
+    }
+
+    /// End a player turn on the map.
+    pub fn end_turn(&mut self) {
+        self.pass_turn();
+        self.check_beaten();
+    }
+
+    /// Let the time of one turn pass.
+    fn pass_turn(&mut self) {
+        let expired = self.player.pass_turn(&self.rules);
+        self.report_expired(expired);
+        self.pass_time(MINUTES_PER_STEP, Activity::Active);
+    }
+
+    /// Injuries can take all Jing away outside of battles too.
+    fn check_beaten(&mut self) {
+        if self.battle.is_none() && self.player.is_beaten() {
+            self.defeat();
+        }
+    }
+
+    fn report_expired(&mut self, expired: Vec<ActiveBuff>) {
+        for buff in expired {
+            self.messages.add_sentence(Msg::markup(
+                MsgType::System,
+                &trf("buff.expired", &[&buff.name(&self.rules.buffs)]),
+            ));
+        }
     }
 
     pub fn on_tick(&mut self, cache: &AssetCache) {
+        // reveal story messages
+        let shown = self.messages.current().map_or(0, |m| m.char_count(self));
+        self.messages.on_tick(shown);
+
+        let errors = self.player.take_formula_errors();
+        self.report_formula_errors(errors);
+
         // check file watchers
         if let Some(map) = &self.curr_map {
-            let (mut map_watcher, mut tile_watcher) = (
+            let (mut map_watcher, mut tile_watcher, mut formula_watcher, mut enemy_watcher) = (
                 cache.load_expect::<World>(map.map_file()).reload_watcher(),
                 cache.load_expect::<Tiles>(map.tile_file()).reload_watcher(),
+                cache.load_expect::<Formulas>("formulas").reload_watcher(),
+                cache
+                    .load_expect::<EnemyLibrary>("enemies")
+                    .reload_watcher(),
             );
+            let mut buff_watcher = cache.load_expect::<BuffLibrary>("buffs").reload_watcher();
+            let mut skill_watcher = cache.load_expect::<SkillLibrary>("skills").reload_watcher();
+            let mut item_watcher = cache.load_expect::<ItemLibrary>("items").reload_watcher();
+            let mut regen_watcher = cache.load_expect::<Regen>("regen").reload_watcher();
+            let mut level_watcher = cache.load_expect::<Levels>("levels").reload_watcher();
+            let mut injury_watcher = cache
+                .load_expect::<InjuryLibrary>("injuries")
+                .reload_watcher();
+            let mut defeat_watcher = cache.load_expect::<Defeat>("defeat").reload_watcher();
+            let mut faction_watcher = cache
+                .load_expect::<FactionLibrary>("factions")
+                .reload_watcher();
+            let mut merchant_watcher = cache
+                .load_expect::<MerchantLibrary>("merchants")
+                .reload_watcher();
+            let mut recipe_watcher = cache
+                .load_expect::<RecipeLibrary>("recipes")
+                .reload_watcher();
+            let mut resource_watcher = cache
+                .load_expect::<ResourceLibrary>("resources")
+                .reload_watcher();
 
             cache.hot_reload();
 
             if map_watcher.reloaded() || tile_watcher.reloaded() {
                 self.load_map(cache);
             }
+
+            if formula_watcher.reloaded() {
+                self.load_formulas(cache);
+                self.player.recompute(&self.rules);
+            }
+
+            if buff_watcher.reloaded() {
+                self.load_buffs(cache);
+                self.player.recompute(&self.rules);
+            }
+
+            if skill_watcher.reloaded() {
+                self.load_skills(cache);
+                self.player.recompute(&self.rules);
+            }
+
+            if item_watcher.reloaded() {
+                self.load_items(cache);
+                self.player.recompute(&self.rules);
+            }
+
+            if enemy_watcher.reloaded() {
+                self.load_enemies(cache);
+            }
+
+            if regen_watcher.reloaded() {
+                self.load_regen(cache);
+            }
+
+            if level_watcher.reloaded() {
+                self.load_levels(cache);
+                self.player.update_level(&self.levels, &self.rules);
+            }
+
+            if injury_watcher.reloaded() {
+                self.load_injuries(cache);
+                self.player.recompute(&self.rules);
+            }
+
+            if defeat_watcher.reloaded() {
+                self.load_defeat(cache);
+            }
+
+            if faction_watcher.reloaded() {
+                self.load_factions(cache);
+            }
+
+            if merchant_watcher.reloaded() {
+                self.load_merchants(cache);
+            }
+
+            if recipe_watcher.reloaded() {
+                self.load_recipes(cache);
+            }
+
+            if resource_watcher.reloaded() {
+                self.load_resources(cache);
+            }
         }
 
         // Check whether the game needs to update
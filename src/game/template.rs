@@ -0,0 +1,178 @@
+use crate::game::{locale::tr, Alignment, GameState, Standing};
+
+/// A placeholder that can appear in message text, e.g. `{player.name}`.
+///
+/// Use `{{` and `}}` to write literal braces.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Placeholder {
+    PlayerName,
+    MapName,
+    MapRegion,
+    Clock,
+    /// Name of the sect of the player.
+    PlayerSect,
+    /// 正, 邪 or neither.
+    PlayerAlignment,
+    /// How a faction feels about the player, e.g. `{standing.village}`.
+    Standing(String),
+    Var(String),
+}
+
+impl Placeholder {
+    /// Parse the key between braces. Return None if the key is unknown.
+    pub fn parse(key: &str) -> Option<Placeholder> {
+        match key {
+            "player.name" => Some(Placeholder::PlayerName),
+            "map.name" => Some(Placeholder::MapName),
+            "map.region" => Some(Placeholder::MapRegion),
+            "clock" => Some(Placeholder::Clock),
+            "player.sect" => Some(Placeholder::PlayerSect),
+            "player.alignment" => Some(Placeholder::PlayerAlignment),
+            _ => {
+                let (prefix, name) = key.split_once('.').filter(|(_, n)| !n.is_empty())?;
+                match prefix {
+                    "var" => Some(Placeholder::Var(name.to_string())),
+                    "standing" => Some(Placeholder::Standing(name.to_string())),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    pub fn resolve(&self, state: &GameState) -> Option<String> {
+        match self {
+            Placeholder::PlayerName => Some(state.player.name().to_string()),
+            Placeholder::MapName => Some(state.world_grid.name.to_string()),
+            Placeholder::MapRegion => Some(state.world_grid.region.to_string()),
+            Placeholder::Clock => Some(state.world_state.clock.to_string()),
+            Placeholder::PlayerSect => Some(
+                state
+                    .player
+                    .reputation()
+                    .sect()
+                    .and_then(|id| state.faction_library.get(id))
+                    .map_or_else(|| tr("faction.none"), |f| f.name.to_string()),
+            ),
+            Placeholder::PlayerAlignment => {
+                Some(Alignment::of(state.player.reputation().alignment()).to_string())
+            }
+            Placeholder::Standing(id) => state
+                .faction_library
+                .get(id)
+                .map(|_| Standing::of(state.player.reputation().get(id)).to_string()),
+            Placeholder::Var(name) => state.variables.get(name).map(|v| v.to_string()),
+        }
+    }
+}
+
+/// A piece of template text.
+enum Segment<'a> {
+    Text(&'a str),
+    Key(&'a str),
+}
+
+/// Split template text into literal text and placeholder keys.
+///
+/// An unclosed `{` is kept as literal text.
+fn segments(text: &str) -> Vec<Segment<'_>> {
+    let mut output = vec![];
+    let mut rest = text;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        let (head, tail) = rest.split_at(start);
+        if !head.is_empty() {
+            output.push(Segment::Text(head));
+        }
+
+        if let Some(after) = tail.strip_prefix("{{").or_else(|| tail.strip_prefix("}}")) {
+            output.push(Segment::Text(&tail[..1]));
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix('}') {
+            output.push(Segment::Text("}"));
+            rest = after;
+        } else if let Some(end) = tail.find('}') {
+            output.push(Segment::Key(&tail[1..end]));
+            rest = &tail[end + 1..];
+        } else {
+            output.push(Segment::Text(tail));
+            rest = "";
+        }
+    }
+
+    if !rest.is_empty() {
+        output.push(Segment::Text(rest));
+    }
+
+    output
+}
+
+/// Replace all placeholders in text with values from the game state.
+///
+/// Placeholders that cannot be resolved are left untouched.
+pub fn render(text: &str, state: &GameState) -> String {
+    segments(text)
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Text(raw) => raw.to_string(),
+            Segment::Key(key) => Placeholder::parse(key)
+                .and_then(|p| p.resolve(state))
+                .unwrap_or_else(|| format!("{{{}}}", key)),
+        })
+        .collect()
+}
+
+/// Find all placeholder keys in text.
+pub fn keys(text: &str) -> Vec<&str> {
+    segments(text)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Key(key) => Some(key),
+            Segment::Text(_) => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Value;
+
+    #[test]
+    fn parse_keys() {
+        assert_eq!(
+            Placeholder::parse("player.name"),
+            Some(Placeholder::PlayerName)
+        );
+        assert_eq!(
+            Placeholder::parse("var.rabbits"),
+            Some(Placeholder::Var("rabbits".to_string()))
+        );
+        assert_eq!(
+            Placeholder::parse("standing.village"),
+            Some(Placeholder::Standing("village".to_string()))
+        );
+        assert_eq!(Placeholder::parse("var."), None);
+        assert_eq!(Placeholder::parse("unknown"), None);
+        assert_eq!(Placeholder::parse("item.rabbit"), None);
+    }
+
+    #[test]
+    fn find_keys() {
+        assert_eq!(
+            keys("{clock}, {player.name}!"),
+            vec!["clock", "player.name"]
+        );
+        assert_eq!(keys("{{not a key}} and {unclosed"), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn render_text() {
+        let mut state = GameState::new();
+        state.variables.set("rabbits", Value::Int(2));
+
+        assert_eq!(render("{var.rabbits} rabbits", &state), "2 rabbits");
+        assert_eq!(render("{{var.rabbits}}", &state), "{var.rabbits}");
+        assert_eq!(render("{var.boars} and }", &state), "{var.boars} and }");
+        assert_eq!(render("open {", &state), "open {");
+    }
+}
@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::components::map::World;
+
+/// A tile of a map replaced while playing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TileChange {
+    pos: (usize, usize),
+    /// Index of the new tile.
+    tile: usize,
+}
+
+/// Changes to the terrain of each map, by map file, such as opened
+/// doors, felled trees and broken walls. Maps are loaded from assets
+/// without them, so they are applied again after every load.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MapDeltas(HashMap<String, Vec<TileChange>>);
+
+impl MapDeltas {
+    /// Record the tile at a position of a map. A later change of the
+    /// same position replaces it.
+    pub fn set(&mut self, map: &str, pos: (usize, usize), tile: usize) {
+        let changes = self.0.entry(map.to_string()).or_default();
+        changes.retain(|c| c.pos != pos);
+        changes.push(TileChange { pos, tile });
+    }
+
+    /// Replace the tiles of a loaded map with the recorded ones.
+    /// Changes outside the map or to unknown tiles are left out, in
+    /// case the assets changed since.
+    pub fn apply(&self, map: &str, world: &mut World) {
+        for change in self.0.get(map).into_iter().flatten() {
+            world.set_tile(change.pos, change.tile);
+        }
+    }
+}
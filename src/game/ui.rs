@@ -1,20 +1,24 @@
-use std::collections::HashMap;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
-use crossterm::event::{Event, KeyCode, KeyEvent};
-use tui::{
-    backend::Backend,
-    layout::Rect,
-    widgets::{Block, Widget},
+use crate::components::{
+    battle::BattleMenu, crafting::CraftingMenu, creation::Creation, dashboard::Dashboard,
+    inventory::InventoryMenu, saves::SaveMenu, settings::SettingsMenu, sheet::CharacterSheet,
+    shop::ShopMenu, Direction, Id,
 };
 
-use crate::components::{dashboard::Dashboard, saves::SaveMenu, Direction, Id};
-
-use super::{GameState, Message, Value};
+use super::{Activity, GameState, Message};
 
 pub struct GameUI {
+    pub battle: BattleMenu,
+    pub crafting: CraftingMenu,
+    pub creation: Option<Creation>,
     pub focus: Id,
     pub dashboard: Dashboard,
+    pub inventory: InventoryMenu,
     pub save_menu: SaveMenu,
+    pub settings_menu: SettingsMenu,
+    pub sheet: CharacterSheet,
+    pub shop: ShopMenu,
 }
 
 impl Default for GameUI {
@@ -23,50 +27,171 @@ impl Default for GameUI {
         let save_path = dirs::data_dir().unwrap().join("wuxia").join("saves");
 
         Self {
+            battle: BattleMenu::default(),
+            crafting: CraftingMenu::default(),
+            creation: None,
             focus: Id::Dashboard,
             dashboard: Dashboard::default(),
+            inventory: InventoryMenu::default(),
             save_menu: SaveMenu::new(&save_path),
+            settings_menu: SettingsMenu::default(),
+            sheet: CharacterSheet::default(),
+            shop: ShopMenu::default(),
         }
     }
 }
 
 impl GameUI {
     // TODO Replace char with KeyEvent. Add Msg callback
-    pub fn on_key(&mut self, code: KeyEvent, state: &mut GameState) {
-        match code {
-            KeyEvent { code, .. } => match code {
-                KeyCode::Char(c) => match self.focus {
-                    Id::Dashboard => match c {
-                        'q' => state.should_quit = true,
-                        _ => self.dashboard.on_key(c),
-                    },
-                    Id::SaveMenu => match c {
-                        'q' => self.focus(Id::Dashboard),
-                        _ => self.save_menu.on_key(c),
-                    },
-                    Id::Map => match c {
-                        'h' => state
-                            .world_grid
-                            .player_move(&mut state.player, Direction::Left),
-                        'l' => state
-                            .world_grid
-                            .player_move(&mut state.player, Direction::Right),
-                        'j' => state
-                            .world_grid
-                            .player_move(&mut state.player, Direction::Down),
-                        'k' => state
-                            .world_grid
-                            .player_move(&mut state.player, Direction::Up),
-                        _ => {}
-                    },
-                    _ => {}
+    pub fn on_key(&mut self, key: KeyEvent, state: &mut GameState) -> Option<Message> {
+        // character creation takes every key for name input
+        if let (Id::Creation, Some(creation)) = (&self.focus, self.creation.as_mut()) {
+            return creation.on_key(key);
+        }
+
+        if let Id::Battle = self.focus {
+            if let Some(action) = self.battle.on_key(key, state) {
+                state.battle_action(action);
+            }
+            return None;
+        }
+
+        match key.code {
+            KeyCode::Char(_) if key.modifiers.contains(KeyModifiers::CONTROL) => {}
+            KeyCode::Char(c) => match self.focus {
+                Id::Dashboard => match c {
+                    'q' => state.should_quit = true,
+                    _ => self.dashboard.on_key(c),
+                },
+                Id::SaveMenu => match c {
+                    'q' => self.focus(Id::Dashboard),
+                    _ => self.save_menu.on_key(c),
+                },
+                Id::Settings => match c {
+                    'q' => self.focus(Id::Dashboard),
+                    _ => self.settings_menu.on_key(c, &mut state.settings),
                 },
+                // pause map movement until the story sequence is read
+                Id::Map if state.messages.is_pending() => state.messages.on_key(c),
+                Id::Map if c == 'c' => self.focus(Id::PlayerInfo),
+                Id::Map if c == 'i' => self.focus(Id::Inventory),
+                Id::Map if c == 'f' => self.focus(Id::Crafting),
+                Id::Map if c == 'g' => state.gather(),
+                Id::Map if c == 'r' => state.rest(Activity::Resting),
+                Id::Map if c == 's' => state.rest(Activity::Sleeping),
+                Id::Map if c == 'm' => state.rest(Activity::Meditating),
+                Id::Map => {
+                    let direction = match c {
+                        'h' => Some(Direction::Left),
+                        'l' => Some(Direction::Right),
+                        'j' => Some(Direction::Down),
+                        'k' => Some(Direction::Up),
+                        _ => None,
+                    };
+
+                    if let Some(direction) = direction {
+                        state.move_player(direction);
+                    }
+                }
+                Id::PlayerInfo => match c {
+                    'c' => self.focus(Id::Map),
+                    'r' => {
+                        if let Some(slot) = self.sheet.selected_slot() {
+                            state.unequip(slot);
+                        }
+                    }
+                    '+' => {
+                        if let Some(attr) = self.sheet.selected_attr() {
+                            state.raise_attr(attr);
+                        }
+                    }
+                    'p' => {
+                        let selected = self.sheet.selected_skill(&state.player);
+                        let id = selected
+                            .and_then(|i| state.player.skills().with(&state.rules.skills).nth(i))
+                            .map(|(s, _)| s.id.clone());
+                        if let Some(id) = id {
+                            state.practice(&id);
+                        }
+                    }
+                    _ => self.sheet.on_key(c, &state.player),
+                },
+                Id::Inventory => match c {
+                    'i' => self.focus(Id::Map),
+                    _ => self.inventory.on_key(c, state),
+                },
+                Id::Crafting => match c {
+                    'f' => self.focus(Id::Map),
+                    _ => self.crafting.on_key(c, state),
+                },
+                Id::Shop => match c {
+                    'q' => state.close_shop(),
+                    _ => self.shop.on_key(c, state),
+                },
+                _ => {}
+            },
+            KeyCode::Esc => match self.focus {
+                Id::PlayerInfo | Id::Inventory | Id::Crafting => self.focus(Id::Map),
+                Id::Shop => state.close_shop(),
                 _ => {}
             },
+            KeyCode::Enter => match self.focus {
+                Id::Dashboard => return self.enter_dashboard(state),
+                Id::SaveMenu => return self.save_menu.selected().map(Message::LoadSave),
+                _ => {}
+            },
+            _ => {}
         }
+
+        None
+    }
+
+    /// Enter the panel of the selected dashboard item.
+    fn enter_dashboard(&mut self, state: &mut GameState) -> Option<Message> {
+        match self.dashboard.selected() {
+            Some(0) => {
+                // saves may have been written or deleted since the start
+                if let Some(dir) = super::save_dir() {
+                    self.save_menu = SaveMenu::new(&dir);
+                }
+                self.focus(Id::SaveMenu);
+            }
+            Some(1) => return Some(Message::OpenCreation),
+            Some(3) => self.focus(Id::Settings),
+            Some(4) => state.should_quit = true,
+            _ => {}
+        }
+
+        None
     }
 
     pub fn focus(&mut self, id: Id) {
         self.focus = id;
     }
+
+    /// Show the battle screen while a battle goes on, and go back to
+    /// the map once it is over.
+    pub fn follow_battle(&mut self, state: &GameState) {
+        match (&self.focus, &state.battle) {
+            (Id::Battle, None) => self.focus(Id::Map),
+            (Id::Map | Id::PlayerInfo | Id::Inventory | Id::Crafting, Some(_)) => {
+                self.battle = BattleMenu::default();
+                self.focus(Id::Battle);
+            }
+            _ => {}
+        }
+    }
+
+    /// Show the shop while the player trades, and go back to the map
+    /// once they leave.
+    pub fn follow_shop(&mut self, state: &GameState) {
+        match (&self.focus, &state.shop) {
+            (Id::Shop, None) => self.focus(Id::Map),
+            (Id::Map, Some(_)) => {
+                self.shop = ShopMenu::default();
+                self.focus(Id::Shop);
+            }
+            _ => {}
+        }
+    }
 }
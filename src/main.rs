@@ -7,6 +7,10 @@ use std::{error::Error, time::Duration};
 use utils::*;
 
 fn main() -> Result<(), Box<dyn Error>> {
+    if std::env::args().any(|arg| arg == "--check") {
+        return check();
+    }
+
     run(Duration::from_millis(250))?;
 
     Ok(())
@@ -11,15 +11,35 @@ use std::{
 };
 use tui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Margin},
     Terminal,
 };
 
 use crate::{
-    components::player::Player,
-    game::{Character, Game, GameMode, GameState, GameUI},
+    components::Id,
+    game::{
+        check::check_assets, locale, settings::Settings, Character, Game, GameMode, GameState,
+        GameUI,
+    },
 };
 
+/// Check all assets and print the problems found.
+pub fn check() -> Result<(), Box<dyn Error>> {
+    let cache: AssetCache = AssetCache::new("assets")?;
+
+    let problems = check_assets(&cache);
+    for problem in problems.iter() {
+        eprintln!("{}", problem);
+    }
+
+    if problems.is_empty() {
+        println!("All assets are fine.");
+        Ok(())
+    } else {
+        Err(format!("found {} problem(s) in assets", problems.len()).into())
+    }
+}
+
 pub fn run(tick_rate: Duration) -> Result<(), Box<dyn Error>> {
     // setup terminal
     enable_raw_mode()?;
@@ -33,7 +53,10 @@ pub fn run(tick_rate: Duration) -> Result<(), Box<dyn Error>> {
 
     // create GameState
     let mut state = GameState::new();
-    state.player = Player::init();
+    state.settings = Settings::load();
+
+    // load strings of the configured language
+    locale::init(&cache, &state.settings.language);
 
     // create GameUI
     let ui = GameUI::default();
@@ -72,6 +95,15 @@ fn run_game<B: Backend>(
                     if let Some(index) = game.ui.dashboard.selected() {
                         match index {
                             0 => game.ui.save_menu.view(f, chunks[0]),
+                            1 => {
+                                if let Some(creation) = &game.ui.creation {
+                                    creation.view(f, chunks[0]);
+                                }
+                            }
+                            3 => game
+                                .ui
+                                .settings_menu
+                                .view(f, &game.state.settings, chunks[0]),
                             _ => {}
                         }
                     }
@@ -80,6 +112,41 @@ fn run_game<B: Backend>(
                 }
                 Some(mode) => match mode {
                     GameMode::Edit => {}
+                    GameMode::Story if matches!(game.ui.focus, Id::Battle) => {
+                        let area = f.size().inner(&Margin {
+                            vertical: 1,
+                            horizontal: 1,
+                        });
+                        game.ui.battle.view(f, &game.state, area);
+                    }
+                    GameMode::Story if matches!(game.ui.focus, Id::Inventory) => {
+                        let area = f.size().inner(&Margin {
+                            vertical: 1,
+                            horizontal: 1,
+                        });
+                        game.ui.inventory.view(f, &game.state, area);
+                    }
+                    GameMode::Story if matches!(game.ui.focus, Id::Crafting) => {
+                        let area = f.size().inner(&Margin {
+                            vertical: 1,
+                            horizontal: 1,
+                        });
+                        game.ui.crafting.view(f, &game.state, area);
+                    }
+                    GameMode::Story if matches!(game.ui.focus, Id::Shop) => {
+                        let area = f.size().inner(&Margin {
+                            vertical: 1,
+                            horizontal: 1,
+                        });
+                        game.ui.shop.view(f, &game.state, area);
+                    }
+                    GameMode::Story if matches!(game.ui.focus, Id::PlayerInfo) => {
+                        let area = f.size().inner(&Margin {
+                            vertical: 1,
+                            horizontal: 1,
+                        });
+                        game.ui.sheet.view(f, &game.state, area);
+                    }
                     GameMode::Story => {
                         let chunks = Layout::default()
                             .direction(Direction::Horizontal)
@@ -106,9 +173,11 @@ fn run_game<B: Backend>(
                                 )
                                 .split(chunks[1]);
 
-                            game.state.messages.draw(f, chunks[0]);
+                            game.state.messages.draw(f, &game.state, chunks[0]);
 
-                            game.state.player.draw_short_desc(f, chunks[1]);
+                            game.state
+                                .player
+                                .draw_short_desc(f, chunks[1], &game.state.rules);
                         }
                     }
                 },